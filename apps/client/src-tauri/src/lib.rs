@@ -26,19 +26,30 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             vault::commands::create_vault,
+            vault::commands::unlock_vault,
+            vault::commands::lock_vault,
+            vault::commands::rekey_vault,
             vault::commands::list_vaults,
             vault::commands::open_vault,
             vault::commands::close_vault,
             vault::commands::delete_vault,
             vault::commands::rename_vault,
             vault::commands::list_entries,
+            vault::commands::list_tree,
+            vault::commands::search_vault,
+            vault::commands::find_by_hash,
+            vault::commands::find_duplicate_notes,
+            vault::commands::export_vault,
+            vault::commands::import_vault,
             vault::commands::read_note,
             vault::commands::edit_note,
+            vault::commands::note_history,
             vault::commands::create_note,
             vault::commands::write_file,
             vault::commands::create_directory,
             vault::commands::delete_entry,
-            vault::commands::rename_entry
+            vault::commands::rename_entry,
+            vault::commands::add_vault_ignore_pattern
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
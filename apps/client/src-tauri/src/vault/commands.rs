@@ -1,7 +1,7 @@
-use crate::vault::{Entry, Vault, VaultManager, VaultWatcher};
+use crate::vault::{Entry, Op, SearchMatch, SearchQuery, Vault, VaultManager, VaultWatcher};
 use anyhow::Result;
 use std::path::Path;
-use tauri::{AppHandle, State};
+use tauri::State;
 use tracing::{error, info};
 use uuid::Uuid;
 
@@ -9,11 +9,45 @@ use uuid::Uuid;
 pub async fn create_vault(
     name: String,
     root_directory: String,
+    passphrase: String,
     manager_state: State<'_, VaultManager>,
 ) -> Result<Vault, String> {
     let location_path = Path::new(&root_directory);
     manager_state
-        .create_vault(location_path, &name)
+        .create_vault(location_path, &name, &passphrase)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    vault_id: Uuid,
+    passphrase: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<(), String> {
+    manager_state
+        .unlock_vault(&vault_id, &passphrase)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    vault_id: Uuid,
+    manager_state: State<'_, VaultManager>,
+) -> Result<(), String> {
+    manager_state.lock_vault(&vault_id).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn rekey_vault(
+    vault_id: Uuid,
+    new_passphrase: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<(), String> {
+    manager_state
+        .rekey_vault(&vault_id, &new_passphrase)
         .await
         .map_err(|e| e.to_string())
 }
@@ -26,7 +60,6 @@ pub async fn list_vaults(manager_state: State<'_, VaultManager>) -> Result<Vec<V
 #[tauri::command]
 pub async fn open_vault(
     vault_path: String,
-    _app_handle: AppHandle,
     manager_state: State<'_, VaultManager>,
     watcher_state: State<'_, VaultWatcher>,
 ) -> Result<Vault, String> {
@@ -36,7 +69,13 @@ pub async fn open_vault(
         .await
         .map_err(|e| e.to_string())?;
 
-    // Start watching the vault for changes
+    if let Err(e) = manager_state.rebuild_index(&vault.id).await {
+        error!("Failed to build search index for vault {}: {}", vault.id, e);
+    }
+
+    // Start watching the vault for changes. The watcher itself keeps the
+    // content index live from this vault's change events instead of
+    // rescanning on every change.
     if let Err(e) = watcher_state
         .watch_vault(vault.id, vault.path.clone())
         .await
@@ -138,7 +177,7 @@ pub async fn edit_note(
     let full_path = vault.path.join(&note_path);
 
     // Mark as pending operation to avoid watcher events
-    watcher_state.mark_pending_operation(full_path).await;
+    watcher_state.mark_pending_operation(vault_id, full_path).await;
 
     manager_state
         .edit_note(&vault_id, &note_path, &content)
@@ -146,6 +185,18 @@ pub async fn edit_note(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn note_history(
+    vault_id: Uuid,
+    note_path: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vec<Op>, String> {
+    manager_state
+        .note_history(&vault_id, &note_path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn create_note(
     vault_id: Uuid,
@@ -165,7 +216,7 @@ pub async fn create_note(
     let full_path = vault.path.join(&note_path);
 
     // Mark as pending operation to avoid watcher events
-    watcher_state.mark_pending_operation(full_path).await;
+    watcher_state.mark_pending_operation(vault_id, full_path).await;
 
     manager_state
         .create_note(&vault_id, &note_path)
@@ -193,7 +244,7 @@ pub async fn write_file(
     let full_path = vault.path.join(&file_path);
 
     // Mark as pending operation to avoid watcher events
-    watcher_state.mark_pending_operation(full_path).await;
+    watcher_state.mark_pending_operation(vault_id, full_path).await;
 
     manager_state
         .edit_note(&vault_id, &file_path, &content)
@@ -220,7 +271,7 @@ pub async fn create_directory(
     let full_path = vault.path.join(&directory_path);
 
     // Mark as pending operation to avoid watcher events
-    watcher_state.mark_pending_operation(full_path).await;
+    watcher_state.mark_pending_operation(vault_id, full_path).await;
 
     manager_state
         .create_directory(&vault_id, &directory_path)
@@ -247,7 +298,7 @@ pub async fn delete_entry(
     let full_path = vault.path.join(&entry_path);
 
     // Mark as pending operation to avoid watcher events
-    watcher_state.mark_pending_operation(full_path).await;
+    watcher_state.mark_pending_operation(vault_id, full_path).await;
 
     manager_state
         .delete_entry(&vault_id, &entry_path)
@@ -255,6 +306,90 @@ pub async fn delete_entry(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub async fn list_tree(
+    vault_id: Uuid,
+    directory_path: Option<String>,
+    max_depth: Option<usize>,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vec<Entry>, String> {
+    manager_state
+        .list_tree(&vault_id, directory_path.as_deref(), max_depth)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn search_vault(
+    vault_id: Uuid,
+    query: SearchQuery,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vec<SearchMatch>, String> {
+    manager_state
+        .search_vault(&vault_id, query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_vault(
+    vault_id: Uuid,
+    dest: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<(), String> {
+    manager_state
+        .export_vault(&vault_id, Path::new(&dest))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn import_vault(
+    archive: String,
+    root_directory: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vault, String> {
+    manager_state
+        .import_vault(Path::new(&archive), Path::new(&root_directory))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn find_by_hash(
+    vault_id: Uuid,
+    hash: String,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vec<String>, String> {
+    manager_state
+        .find_by_hash(&vault_id, &hash)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn find_duplicate_notes(
+    vault_id: Uuid,
+    manager_state: State<'_, VaultManager>,
+) -> Result<Vec<Vec<String>>, String> {
+    manager_state
+        .find_duplicate_notes(&vault_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn add_vault_ignore_pattern(
+    vault_id: Uuid,
+    pattern: String,
+    watcher_state: State<'_, VaultWatcher>,
+) -> Result<(), String> {
+    watcher_state
+        .add_ignore_pattern(&vault_id, &pattern)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn rename_entry(
     vault_id: Uuid,
@@ -276,8 +411,8 @@ pub async fn rename_entry(
     let new_full_path = vault.path.join(&new_path);
 
     // Mark both paths as pending operations to avoid watcher events
-    watcher_state.mark_pending_operation(old_full_path).await;
-    watcher_state.mark_pending_operation(new_full_path).await;
+    watcher_state.mark_pending_operation(vault_id, old_full_path).await;
+    watcher_state.mark_pending_operation(vault_id, new_full_path).await;
 
     manager_state
         .rename_entry(&vault_id, &old_path, &new_path)
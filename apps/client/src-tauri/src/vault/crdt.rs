@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// Unique id for one CRDT character: the replica that created it plus a
+/// Lamport counter, unique within that replica. Ties between two inserts
+/// that share the same left-origin are broken by descending id (see
+/// [`RgaDoc::integrate_insert`]), which is what makes the merge order
+/// deterministic across replicas without any coordination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OpId {
+    pub replica: Uuid,
+    pub counter: u64,
+}
+
+impl PartialOrd for OpId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.counter.cmp(&other.counter).then_with(|| self.replica.cmp(&other.replica))
+    }
+}
+
+/// One entry in a note's append-only operation log: a single character
+/// inserted after `left` (the start of the document if `None`), or the
+/// tombstoning of a previously inserted character. This is what
+/// `VaultManager` persists via `Database::append_note_ops` and replays via
+/// `RgaDoc::from_ops`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Op {
+    Insert { id: OpId, left: Option<OpId>, ch: char },
+    Delete { id: OpId },
+}
+
+impl Op {
+    pub fn id(&self) -> OpId {
+        match self {
+            Op::Insert { id, .. } => *id,
+            Op::Delete { id } => *id,
+        }
+    }
+}
+
+/// One element of the sequence: a character once inserted, which may since
+/// have been tombstoned (`value: None`) by a `Delete` op. Tombstones are
+/// kept (never removed from `elements`) so later ops can still find their
+/// `left` origin even if that origin has since been deleted.
+struct Element {
+    id: OpId,
+    left: Option<OpId>,
+    value: Option<char>,
+}
+
+/// A mergeable text CRDT for one note: an RGA (replicated growable array)
+/// sequence. Concurrent inserts/deletes from different replicas — the
+/// in-app editor and an external editor or sync tool observed through the
+/// watcher — converge to the same visible text regardless of what order
+/// their ops are applied in, so racing edits merge instead of one
+/// clobbering the other.
+#[derive(Default)]
+pub struct RgaDoc {
+    elements: Vec<Element>,
+    /// Next Lamport counter to hand out for a locally generated op. Kept at
+    /// one past the highest counter observed from any replica (including
+    /// merged-in ops), so freshly generated ids stay monotonically
+    /// increasing even across merges.
+    lamport: u64,
+}
+
+impl RgaDoc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Rebuild a document by replaying a persisted op log in append order.
+    pub fn from_ops(ops: &[Op]) -> Self {
+        let mut doc = Self::new();
+        for op in ops {
+            doc.apply(*op);
+        }
+        doc
+    }
+
+    /// The document's current visible text, i.e. every non-tombstoned
+    /// character in sequence order.
+    pub fn text(&self) -> String {
+        self.elements.iter().filter_map(|e| e.value).collect()
+    }
+
+    /// Apply one op, whether generated locally or merged in from another
+    /// replica. Re-applying an insert id that's already present is a
+    /// no-op, so a duplicated or replayed op can't corrupt the document.
+    pub fn apply(&mut self, op: Op) {
+        self.lamport = self.lamport.max(op.id().counter + 1);
+        match op {
+            Op::Insert { id, left, ch } => self.integrate_insert(id, left, ch),
+            Op::Delete { id } => {
+                if let Some(element) = self.elements.iter_mut().find(|e| e.id == id) {
+                    element.value = None;
+                }
+            }
+        }
+    }
+
+    fn position_of(&self, id: OpId) -> Option<usize> {
+        self.elements.iter().position(|e| e.id == id)
+    }
+
+    /// Insert `ch` right after `left` (or at the start if `None`), skipping
+    /// past any other element already sitting there with a larger id — the
+    /// tie-break that makes concurrent inserts at the same origin converge
+    /// to the same order on every replica regardless of delivery order.
+    /// Elements chained after a larger sibling always carry a larger Lamport
+    /// counter than that sibling, so they are skipped along with it.
+    fn integrate_insert(&mut self, id: OpId, left: Option<OpId>, ch: char) {
+        if self.position_of(id).is_some() {
+            return;
+        }
+
+        let mut pos = match left {
+            None => 0,
+            Some(left_id) => self.position_of(left_id).map(|i| i + 1).unwrap_or(0),
+        };
+        while pos < self.elements.len() {
+            let elem = &self.elements[pos];
+            if elem.id > id {
+                pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.elements.insert(pos, Element { id, left, value: Some(ch) });
+    }
+
+    /// Diff `old` (this document's current visible text — the caller must
+    /// pass `self.text()`) against `new`, append the resulting insert/delete
+    /// ops under `replica`, and return them so the caller can persist them
+    /// to the op log. After this call, `self.text() == new`.
+    pub fn diff_and_apply(&mut self, old: &str, new: &str, replica: Uuid) -> Vec<Op> {
+        let old_ids = self.visible_ids();
+        debug_assert_eq!(old_ids.len(), old.chars().count());
+
+        let mut ops = Vec::new();
+        let mut left: Option<OpId> = None;
+        let mut old_index = 0;
+
+        for edit in diff_chars(old, new) {
+            match edit {
+                CharEdit::Retain(_) => {
+                    left = Some(old_ids[old_index]);
+                    old_index += 1;
+                }
+                CharEdit::Delete(_) => {
+                    let op = Op::Delete { id: old_ids[old_index] };
+                    old_index += 1;
+                    self.apply(op);
+                    ops.push(op);
+                    // `left` stays put: the deleted char is gone, so the
+                    // next retained/inserted char still follows whatever
+                    // was last visible before it.
+                }
+                CharEdit::Insert(ch) => {
+                    let id = OpId { replica, counter: self.lamport };
+                    let op = Op::Insert { id, left, ch };
+                    self.apply(op);
+                    ops.push(op);
+                    left = Some(id);
+                }
+            }
+        }
+        ops
+    }
+
+    fn visible_ids(&self) -> Vec<OpId> {
+        self.elements.iter().filter(|e| e.value.is_some()).map(|e| e.id).collect()
+    }
+}
+
+enum CharEdit {
+    Retain(char),
+    Delete(char),
+    Insert(char),
+}
+
+/// Minimal edit script turning `old` into `new`, via the standard
+/// LCS-backed diff. O(n*m) time and space in the two strings' character
+/// counts, which is fine for note-sized text but not for huge files.
+fn diff_chars(old: &str, new: &str) -> Vec<CharEdit> {
+    let old: Vec<char> = old.chars().collect();
+    let new: Vec<char> = new.chars().collect();
+    let n = old.len();
+    let m = new.len();
+
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            edits.push(CharEdit::Retain(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            edits.push(CharEdit::Delete(old[i]));
+            i += 1;
+        } else {
+            edits.push(CharEdit::Insert(new[j]));
+            j += 1;
+        }
+    }
+    while i < n {
+        edits.push(CharEdit::Delete(old[i]));
+        i += 1;
+    }
+    while j < m {
+        edits.push(CharEdit::Insert(new[j]));
+        j += 1;
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_and_apply_produces_target_text() {
+        let mut doc = RgaDoc::new();
+        let ops = doc.diff_and_apply("", "hello", Uuid::now_v7());
+        assert_eq!(ops.len(), 5);
+        assert_eq!(doc.text(), "hello");
+
+        let ops = doc.diff_and_apply(&doc.text(), "help", Uuid::now_v7());
+        assert!(!ops.is_empty());
+        assert_eq!(doc.text(), "help");
+    }
+
+    #[test]
+    fn from_ops_replays_to_the_same_text() {
+        let mut doc = RgaDoc::new();
+        let mut ops = doc.diff_and_apply("", "hello world", Uuid::now_v7());
+        ops.extend(doc.diff_and_apply(&doc.text(), "hello there", Uuid::now_v7()));
+
+        let replayed = RgaDoc::from_ops(&ops);
+        assert_eq!(replayed.text(), doc.text());
+    }
+
+    #[test]
+    fn concurrent_edits_from_different_replicas_converge() {
+        let replica_a = Uuid::now_v7();
+        let replica_b = Uuid::now_v7();
+
+        let mut base = RgaDoc::new();
+        let base_ops = base.diff_and_apply("", "hello world", replica_a);
+
+        // Two replicas independently diverge from the same base.
+        let mut doc_a = RgaDoc::from_ops(&base_ops);
+        let ops_a = doc_a.diff_and_apply(&doc_a.text(), "hello brave world", replica_a);
+
+        let mut doc_b = RgaDoc::from_ops(&base_ops);
+        let ops_b = doc_b.diff_and_apply(&doc_b.text(), "hello world!", replica_b);
+
+        // Merge B's ops into A's replica and vice versa, in different
+        // orders, and both must land on the same text.
+        let mut merged_a = RgaDoc::from_ops(&base_ops);
+        for op in base_ops.iter().chain(ops_a.iter()).chain(ops_b.iter()) {
+            merged_a.apply(*op);
+        }
+
+        let mut merged_b = RgaDoc::from_ops(&base_ops);
+        for op in base_ops.iter().chain(ops_b.iter()).chain(ops_a.iter()) {
+            merged_b.apply(*op);
+        }
+
+        assert_eq!(merged_a.text(), merged_b.text());
+        assert!(merged_a.text().contains("brave"));
+        assert!(merged_a.text().ends_with('!'));
+    }
+
+    #[test]
+    fn replaying_a_delete_without_its_insert_is_a_no_op() {
+        let mut doc = RgaDoc::new();
+        doc.diff_and_apply("", "hello", Uuid::now_v7());
+        let delete_ops = doc.diff_and_apply(&doc.text(), "hllo", Uuid::now_v7());
+        assert_eq!(doc.text(), "hllo");
+
+        let mut ops = Vec::new();
+        ops.extend(delete_ops);
+        let replayed = RgaDoc::from_ops(&ops);
+        // Replaying only the delete (without the original inserts) is a
+        // no-op: there's nothing yet to tombstone.
+        assert_eq!(replayed.text(), "");
+    }
+
+    #[test]
+    fn applying_an_op_twice_is_a_no_op() {
+        let mut doc = RgaDoc::new();
+        let ops = doc.diff_and_apply("", "abc", Uuid::now_v7());
+        for op in &ops {
+            doc.apply(*op);
+        }
+        assert_eq!(doc.text(), "abc");
+    }
+}
@@ -0,0 +1,283 @@
+use anyhow::{anyhow, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::ZeroizeOnDrop;
+
+/// Length of the random salt stored alongside a vault's KDF parameters.
+pub const SALT_LEN: usize = 16;
+/// Length of an XChaCha20-Poly1305 nonce.
+pub const NONCE_LEN: usize = 24;
+/// Length of the vault master key, and of the key derived from a passphrase.
+pub const KEY_LEN: usize = 32;
+
+/// Magic header prefixed to encrypted note bytes on disk, so a note written
+/// before this subsystem existed (or with a different scheme) can be told
+/// apart from one we can decrypt.
+const NOTE_MAGIC: &[u8; 8] = b"INKRYPT1";
+
+/// The 32-byte symmetric key used to encrypt a vault's notes.
+///
+/// Held only in memory by `VaultManager` after `unlock_vault`; never
+/// serialized or written to disk. Zeroized on drop.
+#[derive(Clone, ZeroizeOnDrop)]
+pub struct VaultKey([u8; KEY_LEN]);
+
+impl VaultKey {
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    fn from_bytes(bytes: [u8; KEY_LEN]) -> Self {
+        Self(bytes)
+    }
+
+    fn as_bytes(&self) -> &[u8; KEY_LEN] {
+        &self.0
+    }
+}
+
+/// KDF parameters and the wrapped master key stored, unencrypted except for
+/// the key material itself, in `vault.json`.
+///
+/// The master key is encrypted ("wrapped") under a key derived from the
+/// user's passphrase, so changing the passphrase (`rekey_vault`) only
+/// requires re-wrapping this envelope rather than re-encrypting every note.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoEnvelope {
+    pub salt: Vec<u8>,
+    pub kdf_memory_kib: u32,
+    pub kdf_iterations: u32,
+    pub kdf_parallelism: u32,
+    /// `nonce || ciphertext || tag` for the wrapped 32-byte master key.
+    pub wrapped_key: Vec<u8>,
+    /// HMAC-SHA256 over the fields above, keyed by the passphrase-derived
+    /// key, so a swapped salt or corrupted envelope is detected on unlock
+    /// rather than silently producing garbage key material.
+    pub mac: Vec<u8>,
+}
+
+impl CryptoEnvelope {
+    /// Create a fresh envelope wrapping `master_key` under `passphrase`.
+    pub fn seal(master_key: &VaultKey, passphrase: &str) -> Result<Self> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let kdf_memory_kib = 19_456; // ~19 MiB, matches OWASP's Argon2id baseline
+        let kdf_iterations = 2;
+        let kdf_parallelism = 1;
+
+        let kek = derive_key(
+            passphrase,
+            &salt,
+            kdf_memory_kib,
+            kdf_iterations,
+            kdf_parallelism,
+        )?;
+
+        let wrapped_key = seal_bytes(&kek, master_key.as_bytes())?;
+        let mac = compute_mac(&kek, &salt, kdf_memory_kib, kdf_iterations, kdf_parallelism, &wrapped_key);
+
+        Ok(Self {
+            salt,
+            kdf_memory_kib,
+            kdf_iterations,
+            kdf_parallelism,
+            wrapped_key,
+            mac,
+        })
+    }
+
+    /// Derive the KEK from `passphrase`, verify the envelope's MAC, and
+    /// unwrap the master key. Fails if the passphrase is wrong or the
+    /// envelope (e.g. its salt) was tampered with.
+    pub fn open(&self, passphrase: &str) -> Result<VaultKey> {
+        let kek = derive_key(
+            passphrase,
+            &self.salt,
+            self.kdf_memory_kib,
+            self.kdf_iterations,
+            self.kdf_parallelism,
+        )?;
+
+        let expected_mac = compute_mac(
+            &kek,
+            &self.salt,
+            self.kdf_memory_kib,
+            self.kdf_iterations,
+            self.kdf_parallelism,
+            &self.wrapped_key,
+        );
+        if expected_mac != self.mac {
+            return Err(anyhow!(
+                "vault authentication failed: wrong passphrase or corrupted vault.json"
+            ));
+        }
+
+        let key_bytes = open_bytes(&kek, &self.wrapped_key)
+            .map_err(|_| anyhow!("vault authentication failed: wrong passphrase"))?;
+        let key_bytes: [u8; KEY_LEN] = key_bytes
+            .try_into()
+            .map_err(|_| anyhow!("corrupt vault.json: unwrapped key has the wrong length"))?;
+
+        Ok(VaultKey::from_bytes(key_bytes))
+    }
+
+    /// Re-wrap the already-unwrapped `master_key` under a new passphrase,
+    /// replacing this envelope's salt, KDF parameters, wrapped key and MAC.
+    pub fn reseal(master_key: &VaultKey, new_passphrase: &str) -> Result<Self> {
+        Self::seal(master_key, new_passphrase)
+    }
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<[u8; KEY_LEN]> {
+    let params = Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN))
+        .map_err(|e| anyhow!("invalid Argon2id parameters: {e}"))?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+    let mut out = [0u8; KEY_LEN];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut out)
+        .map_err(|e| anyhow!("key derivation failed: {e}"))?;
+    Ok(out)
+}
+
+fn compute_mac(
+    kek: &[u8; KEY_LEN],
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    wrapped_key: &[u8],
+) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(kek).expect("HMAC accepts any key length");
+    mac.update(salt);
+    mac.update(&memory_kib.to_le_bytes());
+    mac.update(&iterations.to_le_bytes());
+    mac.update(&parallelism.to_le_bytes());
+    mac.update(wrapped_key);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn seal_bytes(key: &[u8; KEY_LEN], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .map_err(|_| anyhow!("encryption failed"))?;
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn open_bytes(key: &[u8; KEY_LEN], sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow!("sealed data is shorter than a nonce"));
+    }
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = XNonce::from_slice(nonce_bytes);
+    cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|_| anyhow!("decryption failed: wrong key or tampered ciphertext"))
+}
+
+/// Encrypt note content for on-disk storage: `MAGIC || nonce || ciphertext || tag`.
+pub fn encrypt_note(key: &VaultKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let sealed = seal_bytes(key.as_bytes(), plaintext)?;
+    let mut out = Vec::with_capacity(NOTE_MAGIC.len() + sealed.len());
+    out.extend_from_slice(NOTE_MAGIC);
+    out.extend_from_slice(&sealed);
+    Ok(out)
+}
+
+/// Decrypt note content previously written by [`encrypt_note`].
+///
+/// Data without the magic header is treated as a legacy plaintext note
+/// (written before this subsystem existed, or never migrated) and returned
+/// as-is, so existing vaults keep working until the note is next saved.
+pub fn decrypt_note(key: &VaultKey, data: &[u8]) -> Result<Vec<u8>> {
+    if !data.starts_with(NOTE_MAGIC) {
+        return Ok(data.to_vec());
+    }
+    open_bytes(key.as_bytes(), &data[NOTE_MAGIC.len()..])
+        .map_err(|_| anyhow!("failed to decrypt note: wrong vault key or tampered file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let key = VaultKey::generate();
+        let envelope = CryptoEnvelope::seal(&key, "correct horse battery staple").unwrap();
+
+        let opened = envelope.open("correct horse battery staple").unwrap();
+        assert_eq!(opened.as_bytes(), key.as_bytes());
+    }
+
+    #[test]
+    fn open_rejects_wrong_passphrase() {
+        let key = VaultKey::generate();
+        let envelope = CryptoEnvelope::seal(&key, "correct horse battery staple").unwrap();
+
+        assert!(envelope.open("wrong passphrase").is_err());
+    }
+
+    #[test]
+    fn open_rejects_tampered_salt() {
+        let key = VaultKey::generate();
+        let mut envelope = CryptoEnvelope::seal(&key, "correct horse battery staple").unwrap();
+        envelope.salt[0] ^= 0xff;
+
+        assert!(envelope.open("correct horse battery staple").is_err());
+    }
+
+    #[test]
+    fn note_round_trip() {
+        let key = VaultKey::generate();
+        let plaintext = b"# Hello\n\nThis is a secret note.";
+
+        let encrypted = encrypt_note(&key, plaintext).unwrap();
+        assert_ne!(&encrypted[NOTE_MAGIC.len()..], &plaintext[..]);
+
+        let decrypted = decrypt_note(&key, &encrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn legacy_plaintext_note_passes_through() {
+        let key = VaultKey::generate();
+        let plaintext = b"unencrypted legacy note";
+
+        let decrypted = decrypt_note(&key, plaintext).unwrap();
+        assert_eq!(decrypted, plaintext);
+    }
+
+    #[test]
+    fn note_decryption_fails_with_wrong_key() {
+        let key = VaultKey::generate();
+        let other_key = VaultKey::generate();
+        let encrypted = encrypt_note(&key, b"top secret").unwrap();
+
+        assert!(decrypt_note(&other_key, &encrypted).is_err());
+    }
+}
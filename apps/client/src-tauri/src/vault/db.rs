@@ -0,0 +1,410 @@
+use crate::vault::crdt::Op;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Schema migrations, applied in order and tracked via `PRAGMA user_version`.
+/// Each entry runs exactly once, in its own transaction, the first time a
+/// database is opened below that version.
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE vaults (
+        id   TEXT PRIMARY KEY,
+        path TEXT NOT NULL
+    );
+    "#,
+    r#"
+    CREATE TABLE note_metadata (
+        vault_id   TEXT NOT NULL,
+        path       TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        title      TEXT,
+        tags       TEXT NOT NULL DEFAULT '[]',
+        PRIMARY KEY (vault_id, path)
+    );
+    "#,
+    r#"
+    CREATE TABLE note_ops (
+        id       INTEGER PRIMARY KEY AUTOINCREMENT,
+        vault_id TEXT NOT NULL,
+        path     TEXT NOT NULL,
+        op       TEXT NOT NULL
+    );
+    CREATE INDEX note_ops_vault_path ON note_ops(vault_id, path, id);
+    "#,
+];
+
+/// Durable facts about one note that should survive a restart without a
+/// full vault rescan. Not to be confused with [`crate::vault::index::NoteMetadata`],
+/// which is the ephemeral, in-memory content-index record that
+/// `VaultManager::rebuild_index` recomputes every time a vault is opened.
+#[derive(Debug, Clone)]
+pub struct NoteRecord {
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// A SQLite-backed store for vault registration and per-note metadata.
+///
+/// Unlike the flat JSON registry it replaces, every multi-statement change
+/// goes through [`Database::transaction`], so two commands racing on the
+/// same vault (e.g. concurrent `create_note`/`rename_entry`) either both
+/// observe a consistent pre- or post-state, never a half-written one.
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    /// Open (creating if necessary) the database file at `path` and bring
+    /// its schema up to date.
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("failed to open database at {}", path.display()))?;
+        let db = Self {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    /// An in-memory database, for tests and `FakeFs`-backed unit tests that
+    /// shouldn't touch real disk.
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory().context("failed to open in-memory database")?;
+        let db = Self {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        db.migrate()?;
+        Ok(db)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let current_version: u32 =
+            conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(current_version as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (i + 1) as u32)?;
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Run `f` inside a `BEGIN`/`COMMIT` transaction, rolling back if it
+    /// returns an error.
+    pub fn transaction<T>(&self, f: impl FnOnce(&rusqlite::Transaction<'_>) -> Result<T>) -> Result<T> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let result = f(&tx)?;
+        tx.commit()?;
+        Ok(result)
+    }
+
+    pub fn upsert_note_metadata(
+        &self,
+        vault_id: Uuid,
+        path: &str,
+        title: Option<&str>,
+        tags: &[String],
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let tags_json = serde_json::to_string(tags)?;
+        self.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO note_metadata (vault_id, path, created_at, updated_at, title, tags)
+                 VALUES (?1, ?2, ?3, ?3, ?4, ?5)
+                 ON CONFLICT(vault_id, path) DO UPDATE SET
+                     updated_at = excluded.updated_at,
+                     title = excluded.title,
+                     tags = excluded.tags",
+                rusqlite::params![vault_id.to_string(), path, now, title, tags_json],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn note_metadata(&self, vault_id: Uuid, path: &str) -> Result<Option<NoteRecord>> {
+        self.transaction(|tx| {
+            tx.query_row(
+                "SELECT created_at, updated_at, title, tags FROM note_metadata
+                 WHERE vault_id = ?1 AND path = ?2",
+                rusqlite::params![vault_id.to_string(), path],
+                |row| {
+                    let created_at: String = row.get(0)?;
+                    let updated_at: String = row.get(1)?;
+                    let title: Option<String> = row.get(2)?;
+                    let tags: String = row.get(3)?;
+                    Ok((created_at, updated_at, title, tags))
+                },
+            )
+            .optional()?
+            .map(|(created_at, updated_at, title, tags)| {
+                Ok(NoteRecord {
+                    created_at: DateTime::parse_from_rfc3339(&created_at)?.with_timezone(&Utc),
+                    updated_at: DateTime::parse_from_rfc3339(&updated_at)?.with_timezone(&Utc),
+                    title,
+                    tags: serde_json::from_str(&tags)?,
+                })
+            })
+            .transpose()
+        })
+    }
+
+    pub fn remove_note_metadata(&self, vault_id: Uuid, path: &str) -> Result<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM note_metadata WHERE vault_id = ?1 AND path = ?2",
+                rusqlite::params![vault_id.to_string(), path],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn remove_vault_note_metadata(&self, vault_id: &Uuid) -> Result<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM note_metadata WHERE vault_id = ?1",
+                rusqlite::params![vault_id.to_string()],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Append ops (see `crate::vault::crdt`) to one note's persisted,
+    /// append-only operation log, in the order given. Each is stored as a
+    /// JSON blob, the same way `note_metadata.tags` stores a JSON array,
+    /// rather than spread across columns per op variant.
+    pub fn append_note_ops(&self, vault_id: Uuid, path: &str, ops: &[Op]) -> Result<()> {
+        self.transaction(|tx| {
+            for op in ops {
+                let op_json = serde_json::to_string(op)?;
+                tx.execute(
+                    "INSERT INTO note_ops (vault_id, path, op) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![vault_id.to_string(), path, op_json],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// The ordered operation log for one note, oldest first, as persisted
+    /// by [`Self::append_note_ops`]. Empty if the note has never been
+    /// edited since this feature shipped.
+    pub fn note_ops(&self, vault_id: Uuid, path: &str) -> Result<Vec<Op>> {
+        self.transaction(|tx| {
+            let mut stmt = tx.prepare(
+                "SELECT op FROM note_ops WHERE vault_id = ?1 AND path = ?2 ORDER BY id",
+            )?;
+            let rows = stmt.query_map(rusqlite::params![vault_id.to_string(), path], |row| {
+                row.get::<_, String>(0)
+            })?;
+
+            let mut ops = Vec::new();
+            for row in rows {
+                ops.push(serde_json::from_str(&row?)?);
+            }
+            Ok(ops)
+        })
+    }
+
+    pub fn remove_note_ops(&self, vault_id: Uuid, path: &str) -> Result<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM note_ops WHERE vault_id = ?1 AND path = ?2",
+                rusqlite::params![vault_id.to_string(), path],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn remove_vault_note_ops(&self, vault_id: &Uuid) -> Result<()> {
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM note_ops WHERE vault_id = ?1",
+                rusqlite::params![vault_id.to_string()],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+/// Vault registration, now durable and transactional: the same thin API
+/// `VaultManager` has always used, backed by a `vaults` table instead of
+/// one JSON file serialized wholesale on every change.
+pub struct VaultRegistry {
+    db: Database,
+}
+
+impl VaultRegistry {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    pub fn insert_vault(&mut self, id: Uuid, path: PathBuf) {
+        self.db
+            .transaction(|tx| {
+                tx.execute(
+                    "INSERT INTO vaults (id, path) VALUES (?1, ?2)
+                     ON CONFLICT(id) DO UPDATE SET path = excluded.path",
+                    rusqlite::params![id.to_string(), path.to_string_lossy().to_string()],
+                )?;
+                Ok(())
+            })
+            .expect("vault registry transaction failed");
+    }
+
+    pub fn remove_vault(&mut self, id: &Uuid) {
+        self.db
+            .transaction(|tx| {
+                tx.execute(
+                    "DELETE FROM vaults WHERE id = ?1",
+                    rusqlite::params![id.to_string()],
+                )?;
+                Ok(())
+            })
+            .expect("vault registry transaction failed");
+        let _ = self.db.remove_vault_note_metadata(id);
+        let _ = self.db.remove_vault_note_ops(id);
+    }
+
+    pub fn get_vault_path(&self, id: &Uuid) -> Option<PathBuf> {
+        self.db
+            .transaction(|tx| {
+                tx.query_row(
+                    "SELECT path FROM vaults WHERE id = ?1",
+                    rusqlite::params![id.to_string()],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(Into::into)
+            })
+            .ok()
+            .flatten()
+            .map(PathBuf::from)
+    }
+
+    pub fn get_vaults(&self) -> HashMap<Uuid, PathBuf> {
+        self.db
+            .transaction(|tx| {
+                let mut stmt = tx.prepare("SELECT id, path FROM vaults")?;
+                let rows = stmt.query_map([], |row| {
+                    let id: String = row.get(0)?;
+                    let path: String = row.get(1)?;
+                    Ok((id, path))
+                })?;
+
+                let mut map = HashMap::new();
+                for row in rows {
+                    let (id, path) = row?;
+                    if let Ok(id) = Uuid::parse_str(&id) {
+                        map.insert(id, PathBuf::from(path));
+                    }
+                }
+                Ok(map)
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_commits_on_success() {
+        let db = Database::open_in_memory().unwrap();
+        db.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO vaults (id, path) VALUES (?1, ?2)",
+                rusqlite::params![Uuid::now_v7().to_string(), "/a"],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let count: u32 = db
+            .transaction(|tx| Ok(tx.query_row("SELECT COUNT(*) FROM vaults", [], |r| r.get(0))?))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn transaction_rolls_back_on_error() {
+        let db = Database::open_in_memory().unwrap();
+        let result: Result<()> = db.transaction(|tx| {
+            tx.execute(
+                "INSERT INTO vaults (id, path) VALUES (?1, ?2)",
+                rusqlite::params![Uuid::now_v7().to_string(), "/a"],
+            )?;
+            Err(anyhow::anyhow!("simulated failure"))
+        });
+        assert!(result.is_err());
+
+        let count: u32 = db
+            .transaction(|tx| Ok(tx.query_row("SELECT COUNT(*) FROM vaults", [], |r| r.get(0))?))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn vault_registry_insert_get_remove() {
+        let db = Database::open_in_memory().unwrap();
+        let mut registry = VaultRegistry::new(db);
+        let id = Uuid::now_v7();
+        let path = PathBuf::from("/vaults/mine");
+
+        registry.insert_vault(id, path.clone());
+        assert_eq!(registry.get_vault_path(&id), Some(path));
+        assert_eq!(registry.get_vaults().len(), 1);
+
+        registry.remove_vault(&id);
+        assert_eq!(registry.get_vault_path(&id), None);
+        assert!(registry.get_vaults().is_empty());
+    }
+
+    #[test]
+    fn note_ops_append_and_remove() {
+        use crate::vault::crdt::OpId;
+
+        let db = Database::open_in_memory().unwrap();
+        let vault_id = Uuid::now_v7();
+        let id = OpId { replica: Uuid::now_v7(), counter: 0 };
+
+        db.append_note_ops(
+            vault_id,
+            "note.md",
+            &[Op::Insert { id, left: None, ch: 'a' }],
+        )
+        .unwrap();
+        let ops = db.note_ops(vault_id, "note.md").unwrap();
+        assert_eq!(ops, vec![Op::Insert { id, left: None, ch: 'a' }]);
+
+        db.remove_note_ops(vault_id, "note.md").unwrap();
+        assert!(db.note_ops(vault_id, "note.md").unwrap().is_empty());
+    }
+
+    #[test]
+    fn note_metadata_upsert_and_remove() {
+        let db = Database::open_in_memory().unwrap();
+        let vault_id = Uuid::now_v7();
+
+        db.upsert_note_metadata(vault_id, "note.md", Some("Title"), &[])
+            .unwrap();
+        let record = db.note_metadata(vault_id, "note.md").unwrap().unwrap();
+        assert_eq!(record.title.as_deref(), Some("Title"));
+        assert!(record.tags.is_empty());
+
+        db.remove_note_metadata(vault_id, "note.md").unwrap();
+        assert!(db.note_metadata(vault_id, "note.md").unwrap().is_none());
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// The subset of filesystem metadata `VaultManager` cares about, independent
+/// of whether it came from `std::fs` or an in-memory fake.
+#[derive(Debug, Clone, Copy)]
+pub struct FsMetadata {
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub len: u64,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+}
+
+/// Filesystem operations needed by the vault layer, abstracted so tests can
+/// run against an in-memory [`FakeFs`] instead of real files on disk, and so
+/// non-local backends can be added later without touching `VaultManager`.
+#[async_trait]
+pub trait Fs: Send + Sync {
+    async fn create_dir_all(&self, path: &Path) -> Result<()>;
+
+    /// Write `contents` to `path`. Implementations should make this
+    /// crash-safe (temp file + rename) where the backing store allows it.
+    async fn write(&self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    async fn read(&self, path: &Path) -> Result<Vec<u8>>;
+
+    async fn read_to_string(&self, path: &Path) -> Result<String> {
+        let bytes = self.read(path).await?;
+        String::from_utf8(bytes).map_err(|e| anyhow!("file is not valid UTF-8: {e}"))
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    async fn remove_dir_all(&self, path: &Path) -> Result<()>;
+
+    async fn remove_file(&self, path: &Path) -> Result<()>;
+
+    /// Direct children of `path` (files and directories, one level deep).
+    async fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>>;
+
+    async fn metadata(&self, path: &Path) -> Result<FsMetadata>;
+
+    async fn exists(&self, path: &Path) -> bool;
+
+    /// Recursively walk `root` and return every descendant path with its
+    /// metadata. `max_depth` caps how far below `root` the walk descends —
+    /// `Some(0)` returns only `root`'s direct children, `None` is unbounded.
+    /// The default implementation visits one directory at a time via
+    /// `read_dir`/`metadata`; [`RealFs`] overrides it with a parallel walker,
+    /// since that's the backend large, real vaults actually hit.
+    async fn walk(&self, root: &Path, max_depth: Option<usize>) -> Result<Vec<(PathBuf, FsMetadata)>> {
+        let mut out = Vec::new();
+        let mut stack = vec![(root.to_path_buf(), 0usize)];
+
+        while let Some((dir, depth)) = stack.pop() {
+            for path in self.read_dir(&dir).await? {
+                let metadata = self.metadata(&path).await?;
+                if metadata.is_dir && max_depth.map_or(true, |max| depth < max) {
+                    stack.push((path.clone(), depth + 1));
+                }
+                out.push((path, metadata));
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+/// `Fs` backed by `std::fs`, matching the on-disk behavior the app shipped
+/// with before this trait existed.
+pub struct RealFs;
+
+impl RealFs {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RealFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Fs for RealFs {
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        fs::create_dir_all(path)?;
+        Ok(())
+    }
+
+    async fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        // Temp-file-then-rename so a crash mid-write can't corrupt `path`;
+        // the temp file lives in the same directory so the rename stays on
+        // one filesystem.
+        let dir = path
+            .parent()
+            .ok_or_else(|| anyhow!("destination path {:?} has no parent directory", path))?;
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("file");
+        let tmp_path = dir.join(format!(".{file_name}.tmp-{}", Uuid::now_v7()));
+
+        let result = (|| -> Result<()> {
+            let mut tmp_file = fs::File::create(&tmp_path)?;
+            tmp_file.write_all(contents)?;
+            tmp_file.sync_all()?;
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+
+        if let Err(e) = fs::rename(&tmp_path, path) {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    async fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(fs::read(path)?)
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to)?;
+        Ok(())
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        fs::remove_dir_all(path)?;
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: &Path) -> Result<()> {
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    async fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(path)? {
+            entries.push(entry?.path());
+        }
+        Ok(entries)
+    }
+
+    async fn metadata(&self, path: &Path) -> Result<FsMetadata> {
+        let metadata = fs::metadata(path)?;
+        Ok(FsMetadata {
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            len: metadata.len(),
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    async fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    async fn walk(&self, root: &Path, max_depth: Option<usize>) -> Result<Vec<(PathBuf, FsMetadata)>> {
+        let root = root.to_path_buf();
+        tokio::task::spawn_blocking(move || walk_parallel(&root, 0, max_depth))
+            .await
+            .map_err(|e| anyhow!("vault walk task panicked: {e}"))?
+    }
+}
+
+/// Parallel directory walk backing `RealFs::walk`: fans sibling entries out
+/// across rayon's thread pool instead of visiting one directory at a time,
+/// so a large vault's tree loads in roughly the depth of the tree rather
+/// than its total entry count. Runs inside `spawn_blocking` (see `walk`
+/// above) since `std::fs` and rayon are both synchronous.
+fn walk_parallel(dir: &Path, depth: usize, max_depth: Option<usize>) -> Result<Vec<(PathBuf, FsMetadata)>> {
+    use rayon::prelude::*;
+
+    let entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+
+    let walked: Result<Vec<Vec<(PathBuf, FsMetadata)>>> = entries
+        .par_iter()
+        .map(|path| {
+            let metadata = fs::metadata(path)?;
+            let fs_metadata = FsMetadata {
+                is_dir: metadata.is_dir(),
+                is_file: metadata.is_file(),
+                len: metadata.len(),
+                created: metadata.created().ok(),
+                modified: metadata.modified().ok(),
+            };
+
+            let mut out = vec![(path.clone(), fs_metadata)];
+            if fs_metadata.is_dir && max_depth.map_or(true, |max| depth < max) {
+                out.extend(walk_parallel(path, depth + 1, max_depth)?);
+            }
+            Ok(out)
+        })
+        .collect();
+
+    Ok(walked?.into_iter().flatten().collect())
+}
+
+#[derive(Debug, Clone)]
+enum FakeNode {
+    Dir,
+    File(Vec<u8>, SystemTime),
+}
+
+/// An in-memory `Fs` fake for tests, modeled as a flat map from path to
+/// node. Lets the vault test suite run fully in-process and in parallel,
+/// with no real temp directories and no `#[serial]` locking, and makes it
+/// possible to inject I/O errors deterministically (see
+/// [`FakeFs::fail_next_write`]).
+pub struct FakeFs {
+    nodes: Mutex<BTreeMap<PathBuf, FakeNode>>,
+    fail_next_write: Mutex<bool>,
+}
+
+impl FakeFs {
+    pub fn new() -> Self {
+        Self {
+            nodes: Mutex::new(BTreeMap::new()),
+            fail_next_write: Mutex::new(false),
+        }
+    }
+
+    /// Make the next call to `write` fail, to exercise error-handling and
+    /// atomic-write rollback paths without needing a real disk fault.
+    pub fn fail_next_write(&self) {
+        *self.fail_next_write.lock().unwrap() = true;
+    }
+
+    fn ensure_ancestors(nodes: &mut BTreeMap<PathBuf, FakeNode>, path: &Path) {
+        let mut ancestor = path.parent();
+        while let Some(dir) = ancestor {
+            if dir.as_os_str().is_empty() {
+                break;
+            }
+            nodes.entry(dir.to_path_buf()).or_insert(FakeNode::Dir);
+            ancestor = dir.parent();
+        }
+    }
+}
+
+impl Default for FakeFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Fs for FakeFs {
+    async fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        Self::ensure_ancestors(&mut nodes, path);
+        nodes.insert(path.to_path_buf(), FakeNode::Dir);
+        Ok(())
+    }
+
+    async fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut should_fail = self.fail_next_write.lock().unwrap();
+        if *should_fail {
+            *should_fail = false;
+            return Err(anyhow!("simulated write failure"));
+        }
+        drop(should_fail);
+
+        let mut nodes = self.nodes.lock().unwrap();
+        Self::ensure_ancestors(&mut nodes, path);
+        nodes.insert(
+            path.to_path_buf(),
+            FakeNode::File(contents.to_vec(), SystemTime::now()),
+        );
+        Ok(())
+    }
+
+    async fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        let nodes = self.nodes.lock().unwrap();
+        match nodes.get(path) {
+            Some(FakeNode::File(bytes, _)) => Ok(bytes.clone()),
+            Some(FakeNode::Dir) => Err(anyhow!("{:?} is a directory", path)),
+            None => Err(anyhow!("{:?} not found", path)),
+        }
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        let moved: Vec<(PathBuf, FakeNode)> = nodes
+            .iter()
+            .filter(|(path, _)| path.starts_with(from))
+            .map(|(path, node)| (path.clone(), node.clone()))
+            .collect();
+
+        if moved.is_empty() {
+            return Err(anyhow!("{:?} not found", from));
+        }
+
+        Self::ensure_ancestors(&mut nodes, to);
+        for (path, node) in moved {
+            nodes.remove(&path);
+            let new_path = to.join(path.strip_prefix(from).unwrap());
+            nodes.insert(new_path, node);
+        }
+        Ok(())
+    }
+
+    async fn remove_dir_all(&self, path: &Path) -> Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        nodes.retain(|p, _| !p.starts_with(path));
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: &Path) -> Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        nodes
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("{:?} not found", path))
+    }
+
+    async fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let nodes = self.nodes.lock().unwrap();
+        let depth = path.components().count() + 1;
+        Ok(nodes
+            .keys()
+            .filter(|p| p.starts_with(path) && p.components().count() == depth)
+            .cloned()
+            .collect())
+    }
+
+    async fn metadata(&self, path: &Path) -> Result<FsMetadata> {
+        let nodes = self.nodes.lock().unwrap();
+        match nodes.get(path) {
+            Some(FakeNode::Dir) => Ok(FsMetadata {
+                is_dir: true,
+                is_file: false,
+                len: 0,
+                created: Some(SystemTime::now()),
+                modified: Some(SystemTime::now()),
+            }),
+            Some(FakeNode::File(bytes, modified)) => Ok(FsMetadata {
+                is_dir: false,
+                is_file: true,
+                len: bytes.len() as u64,
+                created: Some(*modified),
+                modified: Some(*modified),
+            }),
+            None => Err(anyhow!("{:?} not found", path)),
+        }
+    }
+
+    async fn exists(&self, path: &Path) -> bool {
+        self.nodes.lock().unwrap().contains_key(path)
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Metadata captured for one note at index time, keyed by the BLAKE3 hash
+/// of its *decrypted* content — so two notes with identical bodies hash the
+/// same regardless of where they live or how their ciphertext (which uses a
+/// fresh nonce per write, see `crypto::encrypt_note`) happens to differ.
+#[derive(Debug, Clone)]
+pub struct NoteMetadata {
+    pub hash: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub extension: Option<String>,
+    /// Text of the first Markdown heading in the note, if any.
+    pub title: Option<String>,
+}
+
+/// An in-memory, content-addressed index over one vault's notes: per-note
+/// metadata plus a hash-to-paths index for deduplication, kept live by
+/// feeding it `FileSystemEvent`s from `VaultWatcher` instead of doing a full
+/// rescan on every change. Full-text search (`VaultManager::search_vault`)
+/// walks files directly rather than going through this index; see its doc
+/// comment.
+#[derive(Default)]
+pub struct VaultIndex {
+    notes: HashMap<PathBuf, NoteMetadata>,
+    by_hash: HashMap<String, HashSet<PathBuf>>,
+}
+
+impl VaultIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// (Re-)index `path` with decrypted `content`, replacing any previous
+    /// entry for that path.
+    pub fn index_note(
+        &mut self,
+        path: PathBuf,
+        content: &str,
+        size: u64,
+        modified: Option<DateTime<Utc>>,
+    ) {
+        self.remove_note(&path);
+
+        let hash = blake3::hash(content.as_bytes()).to_hex().to_string();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_string);
+        let title = content
+            .lines()
+            .find(|line| line.trim_start().starts_with('#'))
+            .map(|line| line.trim_start_matches('#').trim().to_string());
+
+        self.by_hash.entry(hash.clone()).or_default().insert(path.clone());
+        self.notes.insert(
+            path,
+            NoteMetadata {
+                hash,
+                size,
+                modified,
+                extension,
+                title,
+            },
+        );
+    }
+
+    /// Drop `path` from the index, e.g. on a `Delete` event.
+    pub fn remove_note(&mut self, path: &Path) {
+        if let Some(metadata) = self.notes.remove(path) {
+            if let Some(paths) = self.by_hash.get_mut(&metadata.hash) {
+                paths.remove(path);
+                if paths.is_empty() {
+                    self.by_hash.remove(&metadata.hash);
+                }
+            }
+        }
+    }
+
+    pub fn metadata(&self, path: &Path) -> Option<&NoteMetadata> {
+        self.notes.get(path)
+    }
+
+    /// Vault-relative paths of every note whose decrypted content hashes to
+    /// `hash`, for deduplication.
+    pub fn find_by_hash(&self, hash: &str) -> Vec<PathBuf> {
+        self.by_hash
+            .get(hash)
+            .map(|paths| paths.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Groups of two or more notes that hash identically.
+    pub fn duplicates(&self) -> Vec<Vec<PathBuf>> {
+        self.by_hash
+            .values()
+            .filter(|paths| paths.len() > 1)
+            .map(|paths| paths.iter().cloned().collect())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_note_and_find_by_hash() {
+        let mut index = VaultIndex::new();
+        index.index_note(PathBuf::from("a.md"), "hello world", 11, None);
+        index.index_note(PathBuf::from("b.md"), "hello world", 11, None);
+        index.index_note(PathBuf::from("c.md"), "different", 9, None);
+
+        let hash = index.metadata(Path::new("a.md")).unwrap().hash.clone();
+        let mut matches = index.find_by_hash(&hash);
+        matches.sort();
+        assert_eq!(matches, vec![PathBuf::from("a.md"), PathBuf::from("b.md")]);
+    }
+
+    #[test]
+    fn duplicates_reports_only_groups_with_more_than_one_note() {
+        let mut index = VaultIndex::new();
+        index.index_note(PathBuf::from("a.md"), "same", 4, None);
+        index.index_note(PathBuf::from("b.md"), "same", 4, None);
+        index.index_note(PathBuf::from("c.md"), "unique", 6, None);
+
+        let duplicates = index.duplicates();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].len(), 2);
+    }
+
+    #[test]
+    fn remove_note_clears_its_hash_entry() {
+        let mut index = VaultIndex::new();
+        index.index_note(PathBuf::from("a.md"), "hello world", 11, None);
+        index.remove_note(Path::new("a.md"));
+
+        assert!(index.is_empty());
+        assert!(index.find_by_hash(&blake3::hash(b"hello world").to_hex().to_string()).is_empty());
+    }
+
+    #[test]
+    fn title_is_extracted_from_first_heading() {
+        let mut index = VaultIndex::new();
+        index.index_note(PathBuf::from("a.md"), "# My Title\n\nbody text", 20, None);
+        assert_eq!(
+            index.metadata(Path::new("a.md")).unwrap().title.as_deref(),
+            Some("My Title")
+        );
+    }
+}
@@ -1,15 +1,51 @@
+use crate::vault::crdt::{Op, RgaDoc};
+use crate::vault::crypto::{self, CryptoEnvelope, VaultKey};
+use crate::vault::db::Database;
+use crate::vault::fs::{Fs, FsMetadata, RealFs};
+use crate::vault::index::VaultIndex;
 use crate::vault::models::*;
+use crate::vault::search::{self, Matcher, SearchMatch, SearchQuery};
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use std::fs;
+use globset::Glob;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tokio_stream::StreamExt;
+use tracing::error;
 use uuid::Uuid;
 
+/// Replica id used for CRDT ops diffed from an externally observed change
+/// (another editor, a sync tool) rather than an in-app edit. Fixed rather
+/// than random so every such merge is attributed consistently; it's never
+/// compared for "is this the local replica" purposes, only used as one of
+/// the two sides of a merge.
+const EXTERNAL_REPLICA_ID: Uuid = Uuid::nil();
+
 pub struct VaultManager {
     registry: Arc<RwLock<VaultRegistry>>,
-    registry_path: PathBuf,
+    /// Master keys for vaults that have been unlocked, keyed by vault id.
+    /// Never persisted; dropped (and zeroized) on `lock_vault` or process exit.
+    unlocked_keys: Arc<RwLock<HashMap<Uuid, VaultKey>>>,
+    /// Content-addressed, in-memory index per open vault, used for
+    /// deduplication (full-text search walks files instead). Built by
+    /// `rebuild_index` and kept live by `apply_index_event`.
+    indexes: Arc<RwLock<HashMap<Uuid, VaultIndex>>>,
+    /// This process's replica id for CRDT ops generated by in-app edits
+    /// (see `crate::vault::crdt`). Fixed for the manager's lifetime;
+    /// externally observed edits use `EXTERNAL_REPLICA_ID` instead so the
+    /// two sources merge rather than collide on id.
+    replica_id: Uuid,
+    /// Live per-note CRDT state, keyed by (vault id, vault-relative path).
+    /// Lazily populated from the persisted op log — or seeded from the
+    /// note's current content if it has none yet — the first time a note
+    /// is touched; see `Self::load_doc`.
+    crdt_docs: Arc<RwLock<HashMap<(Uuid, String), RgaDoc>>>,
+    fs: Arc<dyn Fs>,
+    /// Durable store for vault registration (behind `registry`) and
+    /// per-note metadata; see `crate::vault::db`.
+    db: Database,
 }
 
 impl VaultManager {
@@ -18,48 +54,85 @@ impl VaultManager {
         let app_data_dir = data_dir.join("inkrypt");
         std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data directory");
 
-        let registry_path = app_data_dir.join("vaults.json");
-        let registry = if registry_path.exists() {
-            let data =
-                std::fs::read_to_string(&registry_path).expect("Failed to read registry file");
-            serde_json::from_str(&data).expect("Failed to parse registry file")
-        } else {
-            VaultRegistry::new()
+        let db_path = app_data_dir.join("vaults.db");
+        let db = Database::open(&db_path).expect("Failed to open vault database");
+
+        // One-time migration from the flat JSON registry this store replaces.
+        let legacy_registry_path = app_data_dir.join("vaults.json");
+        if legacy_registry_path.exists() {
+            Self::import_legacy_registry(&db, &legacy_registry_path);
+        }
+
+        Self::new_with_db(Arc::new(RealFs::new()), db)
+    }
+
+    /// Best-effort, one-time import of vaults recorded in the old
+    /// `vaults.json` registry, then renames it aside so it isn't reread.
+    fn import_legacy_registry(db: &Database, legacy_registry_path: &Path) {
+        let Ok(data) = std::fs::read_to_string(legacy_registry_path) else {
+            return;
+        };
+        let Ok(legacy_vaults) = serde_json::from_str::<HashMap<Uuid, PathBuf>>(&data) else {
+            return;
         };
 
+        let mut registry = VaultRegistry::new(db.clone());
+        for (id, path) in legacy_vaults {
+            registry.insert_vault(id, path);
+        }
+
+        let _ = std::fs::rename(
+            legacy_registry_path,
+            legacy_registry_path.with_extension("json.bak"),
+        );
+    }
+
+    fn new_with_db(fs: Arc<dyn Fs>, db: Database) -> Self {
         Self {
-            registry: Arc::new(RwLock::new(registry)),
-            registry_path,
+            registry: Arc::new(RwLock::new(VaultRegistry::new(db.clone()))),
+            unlocked_keys: Arc::new(RwLock::new(HashMap::new())),
+            indexes: Arc::new(RwLock::new(HashMap::new())),
+            replica_id: Uuid::now_v7(),
+            crdt_docs: Arc::new(RwLock::new(HashMap::new())),
+            fs,
+            db,
         }
     }
 
+    /// A manager backed by real disk I/O and a real (file-backed) database,
+    /// for integration-style tests that want to exercise the whole stack
+    /// end-to-end, including persistence across manager instances.
     #[cfg(test)]
     pub fn new_for_testing(registry_path: PathBuf) -> Self {
-        let registry = VaultRegistry::new();
-        Self {
-            registry: Arc::new(RwLock::new(registry)),
-            registry_path,
-        }
+        let db = Database::open(&registry_path).expect("Failed to open test vault database");
+        Self::new_with_db(Arc::new(RealFs::new()), db)
     }
 
-    async fn save_registry(&self) -> Result<()> {
-        let registry = self.registry.read().await;
-        let data = serde_json::to_string_pretty(&*registry)?;
-        fs::write(&self.registry_path, data)?;
-        Ok(())
+    /// A manager backed by an arbitrary `Fs` and an in-memory database, so
+    /// unit tests can run against an in-memory [`crate::vault::fs::FakeFs`]
+    /// instead of real temp directories.
+    #[cfg(test)]
+    pub fn new_with_fs(_registry_path: PathBuf, fs: Arc<dyn Fs>) -> Self {
+        let db = Database::open_in_memory().expect("Failed to open in-memory vault database");
+        Self::new_with_db(fs, db)
     }
 
-    pub async fn create_vault(&self, root_directory: &Path, name: &str) -> Result<Vault> {
+    pub async fn create_vault(
+        &self,
+        root_directory: &Path,
+        name: &str,
+        passphrase: &str,
+    ) -> Result<Vault> {
         let vault_path = root_directory.join(name);
 
-        if vault_path.exists() {
+        if self.fs.exists(&vault_path).await {
             return Err(anyhow!("A directory with this name already exists"));
         }
 
         // Create vault directory structure
-        fs::create_dir_all(&vault_path)?;
+        self.fs.create_dir_all(&vault_path).await?;
         let inkrypt_dir = vault_path.join(".inkrypt");
-        fs::create_dir_all(&inkrypt_dir)?;
+        self.fs.create_dir_all(&inkrypt_dir).await?;
 
         // Make the .inkrypt directory hidden on Windows
         #[cfg(windows)]
@@ -70,18 +143,25 @@ impl VaultManager {
                 .expect("failed to set hidden attribute");
         }
 
+        // Generate a fresh master key and wrap it under the passphrase
+        let master_key = VaultKey::generate();
+        let crypto_envelope = CryptoEnvelope::seal(&master_key, passphrase)?;
+
         // Create vault metadata
         let now = Utc::now();
         let vault_metadata = VaultMetadata {
             id: Uuid::now_v7(),
             version: 0,
             created_at: now,
+            crypto: Some(crypto_envelope),
         };
 
         // Save vault.json
         let vault_json_path = inkrypt_dir.join("vault.json");
         let metadata_json = serde_json::to_string_pretty(&vault_metadata)?;
-        fs::write(&vault_json_path, metadata_json)?;
+        self.fs
+            .write(&vault_json_path, metadata_json.as_bytes())
+            .await?;
 
         // Create vault object
         let vault = Vault {
@@ -91,6 +171,7 @@ impl VaultManager {
             version: vault_metadata.version,
             created_at: vault_metadata.created_at,
             updated_at: now,
+            encrypted: true,
         };
 
         // Add to vault registry
@@ -98,11 +179,125 @@ impl VaultManager {
             let mut registry = self.registry.write().await;
             registry.insert_vault(vault_metadata.id, vault_path);
         }
-        self.save_registry().await?;
+
+        // The creator already proved the passphrase by choosing it; leave
+        // the vault unlocked so the first note can be written immediately.
+        {
+            let mut unlocked = self.unlocked_keys.write().await;
+            unlocked.insert(vault.id, master_key);
+        }
 
         Ok(vault)
     }
 
+    /// Derive the vault key from `passphrase` and hold it in memory so
+    /// subsequent `read_note`/`edit_note`/`create_note` calls can
+    /// decrypt/encrypt. Returns an error if the passphrase is wrong.
+    ///
+    /// `open_vault` builds the content index before a passphrase is known,
+    /// so on an encrypted vault every note body is skipped and the index
+    /// comes up empty; rebuild it now that note bodies are decryptable, so
+    /// hash-based dedup isn't silently empty until each note is individually
+    /// edited. Best-effort: a rebuild failure doesn't fail the unlock.
+    pub async fn unlock_vault(&self, vault_id: &Uuid, passphrase: &str) -> Result<()> {
+        let vault_path = {
+            let registry = self.registry.read().await;
+            registry
+                .get_vault_path(vault_id)
+                .ok_or_else(|| anyhow!("Vault not found"))?
+        };
+
+        let metadata = self.read_vault_metadata(&vault_path).await?;
+        let envelope = metadata
+            .crypto
+            .ok_or_else(|| anyhow!("Vault has no crypto envelope and cannot be unlocked"))?;
+        let key = envelope.open(passphrase)?;
+
+        {
+            let mut unlocked = self.unlocked_keys.write().await;
+            unlocked.insert(*vault_id, key);
+        }
+
+        if let Err(e) = self.rebuild_index(vault_id).await {
+            error!(
+                "Failed to rebuild index for vault {} after unlock: {}",
+                vault_id, e
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Drop the in-memory key for a vault, zeroizing it. Subsequent note
+    /// reads/writes fail until the vault is unlocked again.
+    pub async fn lock_vault(&self, vault_id: &Uuid) {
+        let mut unlocked = self.unlocked_keys.write().await;
+        unlocked.remove(vault_id);
+    }
+
+    /// Re-wrap the vault's master key under `new_passphrase` without
+    /// touching any note content. The vault must already be unlocked.
+    pub async fn rekey_vault(&self, vault_id: &Uuid, new_passphrase: &str) -> Result<()> {
+        let vault_path = {
+            let registry = self.registry.read().await;
+            registry
+                .get_vault_path(vault_id)
+                .ok_or_else(|| anyhow!("Vault not found"))?
+        };
+
+        let master_key = {
+            let unlocked = self.unlocked_keys.read().await;
+            unlocked
+                .get(vault_id)
+                .cloned()
+                .ok_or_else(|| anyhow!("Vault is locked; unlock it before rekeying"))?
+        };
+
+        let mut metadata = self.read_vault_metadata(&vault_path).await?;
+        metadata.crypto = Some(CryptoEnvelope::reseal(&master_key, new_passphrase)?);
+
+        let vault_json_path = vault_path.join(".inkrypt").join("vault.json");
+        let metadata_json = serde_json::to_string_pretty(&metadata)?;
+        self.fs
+            .write(&vault_json_path, metadata_json.as_bytes())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn read_vault_metadata(&self, vault_path: &Path) -> Result<VaultMetadata> {
+        let vault_json_path = vault_path.join(".inkrypt").join("vault.json");
+        if !self.fs.exists(&vault_json_path).await {
+            return Err(anyhow!("Not a valid vault: vault.json not found"));
+        }
+        let metadata_json = self.fs.read_to_string(&vault_json_path).await?;
+        Ok(serde_json::from_str(&metadata_json)?)
+    }
+
+    /// The key to use for note content in `vault_id`: `None` for a legacy
+    /// vault with no crypto envelope (notes stay plaintext), `Some(key)`
+    /// once an encrypted vault has been unlocked. Errors if the vault has
+    /// an envelope but hasn't been unlocked yet.
+    async fn note_key(&self, vault_id: &Uuid) -> Result<Option<VaultKey>> {
+        let vault_path = {
+            let registry = self.registry.read().await;
+            registry
+                .get_vault_path(vault_id)
+                .ok_or_else(|| anyhow!("Vault not found"))?
+        };
+        let metadata = self.read_vault_metadata(&vault_path).await?;
+        if metadata.crypto.is_none() {
+            return Ok(None);
+        }
+
+        let unlocked = self.unlocked_keys.read().await;
+        unlocked
+            .get(vault_id)
+            .cloned()
+            .map(Some)
+            .ok_or_else(|| anyhow!("Vault is locked; call unlock_vault first"))
+    }
+
     pub async fn open_vault(&self, vault_path: &Path) -> Result<Vault> {
         let vault = self.load_vault_from_path(vault_path).await?;
 
@@ -111,21 +306,12 @@ impl VaultManager {
             let mut registry = self.registry.write().await;
             registry.insert_vault(vault.id, vault_path.to_path_buf());
         }
-        self.save_registry().await?;
 
         Ok(vault)
     }
 
     async fn load_vault_from_path(&self, vault_path: &Path) -> Result<Vault> {
-        let inkrypt_dir = vault_path.join(".inkrypt");
-        let vault_json_path = inkrypt_dir.join("vault.json");
-
-        if !vault_json_path.exists() {
-            return Err(anyhow!("Not a valid vault: vault.json not found"));
-        }
-
-        let metadata_json = fs::read_to_string(&vault_json_path)?;
-        let metadata: VaultMetadata = serde_json::from_str(&metadata_json)?;
+        let metadata = self.read_vault_metadata(vault_path).await?;
 
         let vault_name = vault_path
             .file_name()
@@ -140,6 +326,7 @@ impl VaultManager {
             version: metadata.version,
             created_at: metadata.created_at,
             updated_at: Utc::now(),
+            encrypted: metadata.crypto.is_some(),
         })
     }
 
@@ -148,10 +335,10 @@ impl VaultManager {
         let mut vaults = Vec::new();
 
         for (id, path) in registry.get_vaults() {
-            match self.load_vault_from_path(path).await {
+            match self.load_vault_from_path(&path).await {
                 Ok(vault) => {
                     // Verify that the loaded vault has the expected ID
-                    if vault.id == *id {
+                    if vault.id == id {
                         vaults.push(vault);
                     } else {
                         // ID mismatch, this vault has been replaced or corrupted
@@ -172,13 +359,13 @@ impl VaultManager {
         // Get the vault path from registry
         let vault_path = {
             let registry = self.registry.read().await;
-            registry.get_vault_path(vault_id).cloned()
+            registry.get_vault_path(vault_id)
         };
 
         if let Some(path) = vault_path {
             // Remove from file system
-            if path.exists() {
-                fs::remove_dir_all(&path)?;
+            if self.fs.exists(&path).await {
+                self.fs.remove_dir_all(&path).await?;
             }
 
             // Remove from registry
@@ -186,7 +373,6 @@ impl VaultManager {
                 let mut registry = self.registry.write().await;
                 registry.remove_vault(vault_id);
             }
-            self.save_registry().await?;
         }
 
         Ok(())
@@ -202,19 +388,18 @@ impl VaultManager {
             .ok_or_else(|| anyhow!("Cannot get parent directory"))?;
         let new_path = parent.join(new_name);
 
-        if new_path.exists() {
+        if self.fs.exists(&new_path).await {
             return Err(anyhow!("A directory with this name already exists"));
         }
 
         // Rename the directory in the file system
-        fs::rename(&vault.path, &new_path)?;
+        self.fs.rename(&vault.path, &new_path).await?;
 
         // Update vault registry with new path
         {
             let mut registry = self.registry.write().await;
             registry.insert_vault(vault.id, new_path.clone());
         }
-        self.save_registry().await?;
 
         // Return updated vault
         let updated_vault = Vault {
@@ -224,6 +409,7 @@ impl VaultManager {
             version: vault.version,
             created_at: vault.created_at,
             updated_at: Utc::now(),
+            encrypted: vault.encrypted,
         };
 
         Ok(updated_vault)
@@ -233,7 +419,7 @@ impl VaultManager {
         let registry = self.registry.read().await;
 
         if let Some(path) = registry.get_vault_path(vault_id) {
-            self.load_vault_from_path(path).await
+            self.load_vault_from_path(&path).await
         } else {
             Err(anyhow!("Vault not found"))
         }
@@ -242,7 +428,7 @@ impl VaultManager {
     pub async fn create_directory(&self, vault_id: &Uuid, directory_path: &str) -> Result<()> {
         let vault = self.find_vault_by_id(vault_id).await?;
         let full_path = vault.path.join(directory_path);
-        fs::create_dir_all(full_path)?;
+        self.fs.create_dir_all(&full_path).await?;
         Ok(())
     }
 
@@ -252,36 +438,170 @@ impl VaultManager {
 
         // Ensure parent directory exists
         if let Some(parent) = full_path.parent() {
-            fs::create_dir_all(parent)?;
+            self.fs.create_dir_all(parent).await?;
         }
 
-        // Create empty note file
-        fs::write(&full_path, "")?;
+        // Create empty note file, encrypted like any other note content
+        // unless this is a legacy vault with no crypto envelope.
+        let key = self.note_key(vault_id).await?;
+        let bytes = match &key {
+            Some(key) => crypto::encrypt_note(key, b"")?,
+            None => Vec::new(),
+        };
+        self.fs.write(&full_path, &bytes).await?;
         Ok(())
     }
 
     pub async fn edit_note(&self, vault_id: &Uuid, note_path: &str, content: &str) -> Result<()> {
         let vault = self.find_vault_by_id(vault_id).await?;
         let full_path = vault.path.join(note_path);
-        fs::write(&full_path, content)?;
+
+        // Diff against the note's CRDT state (rather than overwriting
+        // outright) so a concurrent external edit merges instead of being
+        // silently clobbered; see `crate::vault::crdt`.
+        let merged = self
+            .apply_local_edit(vault_id, note_path, &full_path, content)
+            .await?;
+
+        let key = self.note_key(vault_id).await?;
+        let bytes = match &key {
+            Some(key) => crypto::encrypt_note(key, merged.as_bytes())?,
+            None => merged.into_bytes(),
+        };
+        self.fs.write(&full_path, &bytes).await?;
+        Ok(())
+    }
+
+    /// Diff `content` against this note's current CRDT state, append the
+    /// resulting ops under this process's replica id, and return the
+    /// merged text to write to disk. Loads (or seeds) the document on
+    /// first touch via [`Self::load_doc`].
+    async fn apply_local_edit(
+        &self,
+        vault_id: &Uuid,
+        note_path: &str,
+        full_path: &Path,
+        content: &str,
+    ) -> Result<String> {
+        let mut docs = self.crdt_docs.write().await;
+        let doc_key = (*vault_id, note_path.to_string());
+        if !docs.contains_key(&doc_key) {
+            let doc = self.load_doc(vault_id, note_path, full_path).await?;
+            docs.insert(doc_key.clone(), doc);
+        }
+        let doc = docs.get_mut(&doc_key).expect("just inserted if missing");
+
+        let old = doc.text();
+        let ops = doc.diff_and_apply(&old, content, self.replica_id);
+        if !ops.is_empty() {
+            self.db.append_note_ops(*vault_id, note_path, &ops)?;
+        }
+        Ok(doc.text())
+    }
+
+    /// Merge an externally observed change (another editor, a sync tool)
+    /// into this note's CRDT state instead of letting it clobber — or be
+    /// clobbered by — a concurrent in-app edit. Diffs the file's current
+    /// (decrypted) bytes against the document under `EXTERNAL_REPLICA_ID`
+    /// and, if the merge result differs from what's on disk, rewrites the
+    /// file so both sides converge on the same text.
+    pub async fn merge_external_change(&self, event: &FileSystemEvent) -> Result<()> {
+        if !matches!(event.event_type, FileEventType::Create | FileEventType::Modify) {
+            return Ok(());
+        }
+        if event.entry_type != Some(EntryType::Note) {
+            return Ok(());
+        }
+
+        let vault = self.find_vault_by_id(&event.vault_id).await?;
+        let full_path = vault.path.join(&event.path);
+        let key = self.note_key(&event.vault_id).await.ok().flatten();
+        let Some(text) = self.decrypted_text(&full_path, &key).await else {
+            return Ok(());
+        };
+
+        let mut docs = self.crdt_docs.write().await;
+        let doc_key = (event.vault_id, event.path.clone());
+        if !docs.contains_key(&doc_key) {
+            let doc = self.load_doc(&event.vault_id, &event.path, &full_path).await?;
+            docs.insert(doc_key.clone(), doc);
+        }
+        let doc = docs.get_mut(&doc_key).expect("just inserted if missing");
+
+        let old = doc.text();
+        if old == text {
+            return Ok(());
+        }
+        let ops = doc.diff_and_apply(&old, &text, EXTERNAL_REPLICA_ID);
+        if !ops.is_empty() {
+            self.db.append_note_ops(event.vault_id, &event.path, &ops)?;
+        }
+        let merged = doc.text();
+        drop(docs);
+
+        if merged != text {
+            let bytes = match &key {
+                Some(key) => crypto::encrypt_note(key, merged.as_bytes())?,
+                None => merged.into_bytes(),
+            };
+            self.fs.write(&full_path, &bytes).await?;
+        }
         Ok(())
     }
 
+    /// Load a note's CRDT document from its persisted op log, seeding it
+    /// from the note's current on-disk content if the log is empty (e.g.
+    /// the first time a pre-existing note is touched after this feature
+    /// shipped). The seed ops are attributed to the local replica and
+    /// persisted immediately so the seed only happens once.
+    async fn load_doc(&self, vault_id: &Uuid, note_path: &str, full_path: &Path) -> Result<RgaDoc> {
+        let ops = self.db.note_ops(*vault_id, note_path)?;
+        if !ops.is_empty() {
+            return Ok(RgaDoc::from_ops(&ops));
+        }
+
+        let mut doc = RgaDoc::new();
+        if self.fs.exists(full_path).await {
+            let key = self.note_key(vault_id).await.ok().flatten();
+            if let Some(text) = self.decrypted_text(full_path, &key).await {
+                if !text.is_empty() {
+                    let seed_ops = doc.diff_and_apply("", &text, self.replica_id);
+                    self.db.append_note_ops(*vault_id, note_path, &seed_ops)?;
+                }
+            }
+        }
+        Ok(doc)
+    }
+
+    /// Ordered operation log for one note, for undo/audit: every insert and
+    /// delete ever applied to it, from any replica, in append order.
+    pub async fn note_history(&self, vault_id: &Uuid, note_path: &str) -> Result<Vec<Op>> {
+        self.db.note_ops(*vault_id, note_path)
+    }
+
     pub async fn read_note(&self, vault_id: &Uuid, note_path: &str) -> Result<String> {
         let vault = self.find_vault_by_id(vault_id).await?;
         let full_path = vault.path.join(note_path);
-        let content = fs::read_to_string(&full_path)?;
-        Ok(content)
+
+        let key = self.note_key(vault_id).await?;
+        let raw = self.fs.read(&full_path).await?;
+        let decrypted = match &key {
+            Some(key) => crypto::decrypt_note(key, &raw)?,
+            None => raw,
+        };
+        Ok(String::from_utf8(decrypted)?)
     }
 
     pub async fn delete_entry(&self, vault_id: &Uuid, entry_path: &str) -> Result<()> {
         let vault = self.find_vault_by_id(vault_id).await?;
         let full_path = vault.path.join(entry_path);
 
-        if full_path.is_dir() {
-            fs::remove_dir_all(&full_path)?;
-        } else if full_path.is_file() {
-            fs::remove_file(&full_path)?;
+        if let Ok(metadata) = self.fs.metadata(&full_path).await {
+            if metadata.is_dir {
+                self.fs.remove_dir_all(&full_path).await?;
+            } else if metadata.is_file {
+                self.fs.remove_file(&full_path).await?;
+            }
         }
 
         Ok(())
@@ -299,10 +619,10 @@ impl VaultManager {
 
         // Ensure parent directory exists for new path
         if let Some(parent) = new_full_path.parent() {
-            fs::create_dir_all(parent)?;
+            self.fs.create_dir_all(parent).await?;
         }
 
-        fs::rename(&old_full_path, &new_full_path)?;
+        self.fs.rename(&old_full_path, &new_full_path).await?;
         Ok(())
     }
 
@@ -320,10 +640,7 @@ impl VaultManager {
 
         let mut entries = Vec::new();
 
-        for entry in fs::read_dir(&base_path)? {
-            let entry = entry?;
-            let path = entry.path();
-
+        for path in self.fs.read_dir(&base_path).await? {
             // Skip hidden files and .inkrypt directory
             let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
 
@@ -331,110 +648,599 @@ impl VaultManager {
                 continue;
             }
 
-            let metadata = entry.metadata()?;
+            let metadata = self.fs.metadata(&path).await?;
             let relative_path = path
                 .strip_prefix(&vault.path)?
                 .to_string_lossy()
                 .replace('\\', "/");
 
-            let entry_type = if metadata.is_dir() {
+            let entry_type = if metadata.is_dir {
                 EntryType::Directory
             } else {
                 EntryType::Note
             };
 
-            let created_at = metadata
-                .created()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| DateTime::from_timestamp(d.as_secs() as i64, 0))
-                .flatten()
-                .map(|dt| dt.with_timezone(&Utc));
-
-            let updated_at = metadata
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| DateTime::from_timestamp(d.as_secs() as i64, 0))
-                .flatten()
-                .map(|dt| dt.with_timezone(&Utc));
-
             entries.push(Entry {
                 name: file_name.to_string(),
                 path: relative_path,
                 entry_type,
-                created_at,
-                updated_at,
+                created_at: system_time_to_utc(metadata.created),
+                updated_at: system_time_to_utc(metadata.modified),
+                children: None,
             });
         }
 
-        // Sort: directories first, then alphabetically
-        entries.sort_by(|a, b| match (&a.entry_type, &b.entry_type) {
-            (EntryType::Directory, EntryType::Note) => std::cmp::Ordering::Less,
-            (EntryType::Note, EntryType::Directory) => std::cmp::Ordering::Greater,
-            _ => a.name.cmp(&b.name),
-        });
+        sort_entries(&mut entries);
 
         Ok(entries)
     }
 
-    #[cfg(test)]
-    pub async fn load_registry_for_testing(&self) -> Result<()> {
-        if self.registry_path.exists() {
-            let data = std::fs::read_to_string(&self.registry_path)?;
-            let loaded_registry: VaultRegistry = serde_json::from_str(&data)?;
+    /// Recursively list the contents of `directory_path` (the whole vault if
+    /// `None`), returning each directory's contents as its `children`, so
+    /// the UI can render a nested tree in one call. `max_depth` caps how far
+    /// below `directory_path` the tree descends (`Some(0)` returns only its
+    /// direct children, `None` is unbounded). Entries matched by an
+    /// `.inkryptignore` file at the vault root (gitignore syntax) are
+    /// skipped, in addition to the usual dot-file/`.inkrypt` exclusion.
+    ///
+    /// Walks via `Fs::walk`, which `RealFs` parallelizes and runs off the
+    /// async runtime, so a large vault's tree loads in one call instead of
+    /// one `read_dir` round-trip per directory level.
+    pub async fn list_tree(
+        &self,
+        vault_id: &Uuid,
+        directory_path: Option<&str>,
+        max_depth: Option<usize>,
+    ) -> Result<Vec<Entry>> {
+        let vault = self.find_vault_by_id(vault_id).await?;
+        let base_path = match directory_path {
+            Some(directory) => vault.path.join(directory),
+            None => vault.path.clone(),
+        };
+
+        let ignore = self.load_ignore_matcher(&vault.path).await?;
+
+        let mut metadata_by_path: HashMap<PathBuf, FsMetadata> = HashMap::new();
+        let mut children_by_dir: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+
+        for (path, metadata) in self.fs.walk(&base_path, max_depth).await? {
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if file_name.starts_with('.') {
+                continue;
+            }
+
+            if let Some(ignore) = &ignore {
+                let Ok(relative) = path.strip_prefix(&vault.path) else {
+                    continue;
+                };
+                if ignore.matched(relative, metadata.is_dir).is_ignore() {
+                    continue;
+                }
+            }
+
+            let Some(parent) = path.parent() else {
+                continue;
+            };
+            children_by_dir.entry(parent.to_path_buf()).or_default().push(path.clone());
+            metadata_by_path.insert(path, metadata);
+        }
+
+        Ok(self.build_tree_level(&base_path, &vault.path, &metadata_by_path, &children_by_dir))
+    }
+
+    /// Build the `Entry` list for one directory level of [`Self::list_tree`],
+    /// recursing into subdirectories using the already-collected metadata.
+    fn build_tree_level(
+        &self,
+        dir: &Path,
+        vault_root: &Path,
+        metadata_by_path: &HashMap<PathBuf, FsMetadata>,
+        children_by_dir: &HashMap<PathBuf, Vec<PathBuf>>,
+    ) -> Vec<Entry> {
+        let Some(child_paths) = children_by_dir.get(dir) else {
+            return Vec::new();
+        };
+
+        let mut entries: Vec<Entry> = child_paths
+            .iter()
+            .map(|path| {
+                let metadata = metadata_by_path[path];
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                let relative_path = path
+                    .strip_prefix(vault_root)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+
+                let entry_type = if metadata.is_dir {
+                    EntryType::Directory
+                } else {
+                    EntryType::Note
+                };
+
+                let children = if metadata.is_dir {
+                    Some(self.build_tree_level(path, vault_root, metadata_by_path, children_by_dir))
+                } else {
+                    None
+                };
+
+                Entry {
+                    name: name.to_string(),
+                    path: relative_path,
+                    entry_type,
+                    created_at: system_time_to_utc(metadata.created),
+                    updated_at: system_time_to_utc(metadata.modified),
+                    children,
+                }
+            })
+            .collect();
+
+        sort_entries(&mut entries);
+        entries
+    }
+
+    /// Load `.inkryptignore` from the vault root, if present, compiled as a
+    /// gitignore-style matcher. Reads via `self.fs` (not `std::fs` directly)
+    /// so it works against both real and fake filesystems.
+    async fn load_ignore_matcher(&self, vault_root: &Path) -> Result<Option<ignore::gitignore::Gitignore>> {
+        let ignore_path = vault_root.join(".inkryptignore");
+        if !self.fs.exists(&ignore_path).await {
+            return Ok(None);
+        }
+
+        let content = self.fs.read_to_string(&ignore_path).await?;
+        let mut builder = ignore::gitignore::GitignoreBuilder::new(vault_root);
+        for line in content.lines() {
+            builder.add_line(None, line)?;
+        }
+
+        Ok(Some(builder.build()?))
+    }
+
+    /// Recursively search note names and decrypted contents under `vault_id`.
+    /// Honors the same dot-file/`.inkrypt` exclusion as [`Self::list_entries`].
+    /// Walks files directly rather than querying [`VaultIndex`]; the index's
+    /// job is content-addressed deduplication ([`Self::find_duplicate_notes`]),
+    /// not full-text search.
+    pub async fn search_vault(&self, vault_id: &Uuid, query: SearchQuery) -> Result<Vec<SearchMatch>> {
+        let vault = self.find_vault_by_id(vault_id).await?;
+        let key = self.note_key(vault_id).await.ok().flatten();
+        let matcher = Matcher::new(&query)?;
+        let path_glob = query
+            .path_glob
+            .as_deref()
+            .map(Glob::new)
+            .transpose()?
+            .map(|glob| glob.compile_matcher());
+
+        let files = self
+            .collect_files(&vault.path, &vault.path, 0, query.max_depth, path_glob.as_ref())
+            .await?;
+
+        let mut matches = Vec::new();
+        'files: for path in files {
+            let relative = path
+                .strip_prefix(&vault.path)?
+                .to_string_lossy()
+                .replace('\\', "/");
+
+            let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            if matcher.is_match(file_name) {
+                matches.push(SearchMatch {
+                    path: relative.clone(),
+                    line: None,
+                    snippet: file_name.to_string(),
+                });
+                if Some(matches.len()) == query.max_results {
+                    break 'files;
+                }
+            }
+
+            let Ok(raw) = self.fs.read(&path).await else {
+                continue;
+            };
+            let content = match &key {
+                Some(key) => match crypto::decrypt_note(key, &raw) {
+                    Ok(bytes) => bytes,
+                    Err(_) => continue,
+                },
+                None => raw,
+            };
+            let Ok(text) = String::from_utf8(content) else {
+                continue;
+            };
+
+            for (line_number, line) in text.lines().enumerate() {
+                if matcher.is_match(line) {
+                    matches.push(SearchMatch {
+                        path: relative.clone(),
+                        line: Some(line_number + 1),
+                        snippet: search::snippet(line),
+                    });
+                    if Some(matches.len()) == query.max_results {
+                        break 'files;
+                    }
+                }
+            }
+        }
+
+        Ok(matches)
+    }
+
+    /// Depth-first walk of `dir` collecting file paths, skipping dot-prefixed
+    /// entries and anything that doesn't pass `glob` (matched against the
+    /// vault-relative path). Iterative rather than recursive-async so it
+    /// doesn't need a boxed future.
+    async fn collect_files(
+        &self,
+        dir: &Path,
+        vault_root: &Path,
+        depth: usize,
+        max_depth: Option<usize>,
+        glob: Option<&globset::GlobMatcher>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut stack = vec![(dir.to_path_buf(), depth)];
+
+        while let Some((current_dir, current_depth)) = stack.pop() {
+            if let Some(max) = max_depth {
+                if current_depth > max {
+                    continue;
+                }
+            }
+
+            for path in self.fs.read_dir(&current_dir).await? {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                if name.starts_with('.') {
+                    continue;
+                }
+
+                let metadata = self.fs.metadata(&path).await?;
+                if metadata.is_dir {
+                    stack.push((path, current_depth + 1));
+                    continue;
+                }
+
+                if let Some(glob) = glob {
+                    let relative = path.strip_prefix(vault_root).unwrap_or(&path);
+                    if !glob.is_match(relative) {
+                        continue;
+                    }
+                }
+
+                files.push(path);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Walk the whole vault and build a fresh content-addressed index,
+    /// replacing any previous one. Called on `open_vault` and again by
+    /// `unlock_vault`; afterwards
+    /// the index is kept live by feeding it watcher events via
+    /// [`Self::apply_index_event`] instead of rescanning. If the vault has
+    /// a crypto envelope but is locked, note bodies are skipped (so hashes
+    /// go stale until the next unlock + rebuild); indexing doesn't require
+    /// a rebuild to fail just because the vault is locked.
+    pub async fn rebuild_index(&self, vault_id: &Uuid) -> Result<()> {
+        let vault = self.find_vault_by_id(vault_id).await?;
+        let key = self.note_key(vault_id).await.ok().flatten();
+
+        let files = self.collect_files(&vault.path, &vault.path, 0, None, None).await?;
+        let mut index = VaultIndex::new();
+
+        for path in files {
+            let Ok(relative) = path.strip_prefix(&vault.path) else {
+                continue;
+            };
+            let Some(text) = self.decrypted_text(&path, &key).await else {
+                continue;
+            };
+            let metadata = self.fs.metadata(&path).await.ok();
+            index.index_note(
+                relative.to_path_buf(),
+                &text,
+                metadata.map(|m| m.len).unwrap_or(0),
+                metadata.and_then(|m| system_time_to_utc(m.modified)),
+            );
+
+            // Durable note facts (title) live in the database so they're
+            // available without a full rescan; the content hash stays in
+            // the in-memory `VaultIndex` above, which is cheap to rebuild.
+            let relative_str = relative.to_string_lossy().replace('\\', "/");
+            if let Some(note) = index.metadata(relative) {
+                let _ = self
+                    .db
+                    .upsert_note_metadata(*vault_id, &relative_str, note.title.as_deref(), &[]);
+            }
+        }
+
+        let mut indexes = self.indexes.write().await;
+        indexes.insert(*vault_id, index);
+        Ok(())
+    }
+
+    async fn decrypted_text(&self, path: &Path, key: &Option<VaultKey>) -> Option<String> {
+        let raw = self.fs.read(path).await.ok()?;
+        let content = match key {
+            Some(key) => crypto::decrypt_note(key, &raw).ok()?,
+            None => raw,
+        };
+        String::from_utf8(content).ok()
+    }
+
+    /// Update the live index for one note in response to a watcher-reported
+    /// change, instead of rescanning the whole vault. No-op if the vault
+    /// has never been indexed (e.g. `rebuild_index` hasn't run yet).
+    pub async fn apply_index_event(&self, event: &FileSystemEvent) {
+        let mut indexes = self.indexes.write().await;
+        let Some(index) = indexes.get_mut(&event.vault_id) else {
+            return;
+        };
+        let path = PathBuf::from(&event.path);
+
+        match &event.event_type {
+            FileEventType::Delete => {
+                index.remove_note(&path);
+                let relative_str = path.to_string_lossy().replace('\\', "/");
+                let _ = self.db.remove_note_metadata(event.vault_id, &relative_str);
+                let _ = self.db.remove_note_ops(event.vault_id, &relative_str);
+                drop(indexes);
+                let mut docs = self.crdt_docs.write().await;
+                docs.remove(&(event.vault_id, relative_str));
+                return;
+            }
+            FileEventType::Rename { from, .. } => {
+                // The old path's note is gone under that name; drop its
+                // state before reindexing under the new one, same as a
+                // Delete would, rather than leaving it orphaned.
+                index.remove_note(&PathBuf::from(from));
+                let _ = self.db.remove_note_metadata(event.vault_id, from);
+                let _ = self.db.remove_note_ops(event.vault_id, from);
+                drop(indexes);
+                let mut docs = self.crdt_docs.write().await;
+                docs.remove(&(event.vault_id, from.clone()));
+                drop(docs);
+                self.reindex_note(event, &path).await;
+            }
+            FileEventType::Create | FileEventType::Modify => {
+                drop(indexes);
+                self.reindex_note(event, &path).await;
+            }
+        }
+    }
+
+    /// Decrypt and re-insert one note into its vault's live index, the
+    /// shared tail of [`Self::apply_index_event`]'s Create/Modify/Rename
+    /// handling.
+    async fn reindex_note(&self, event: &FileSystemEvent, path: &Path) {
+        let key = self.note_key(&event.vault_id).await.ok().flatten();
+        let Some(vault) = self.find_vault_by_id(&event.vault_id).await.ok() else {
+            return;
+        };
+        let Some(text) = self.decrypted_text(&vault.path.join(path), &key).await else {
+            return;
+        };
+        let metadata = self.fs.metadata(&vault.path.join(path)).await.ok();
+        let mut indexes = self.indexes.write().await;
+        if let Some(index) = indexes.get_mut(&event.vault_id) {
+            index.index_note(
+                path.to_path_buf(),
+                &text,
+                metadata.map(|m| m.len).unwrap_or(0),
+                metadata.and_then(|m| system_time_to_utc(m.modified)),
+            );
+            let relative_str = path.to_string_lossy().replace('\\', "/");
+            if let Some(note) = index.metadata(path) {
+                let _ = self.db.upsert_note_metadata(
+                    event.vault_id,
+                    &relative_str,
+                    note.title.as_deref(),
+                    &[],
+                );
+            }
+        }
+    }
+
+    /// Vault-relative paths of every note whose decrypted content hashes
+    /// the same as `hash`, for deduplication.
+    pub async fn find_by_hash(&self, vault_id: &Uuid, hash: &str) -> Result<Vec<String>> {
+        let indexes = self.indexes.read().await;
+        let index = indexes
+            .get(vault_id)
+            .ok_or_else(|| anyhow!("vault has not been indexed yet; open it first"))?;
+        Ok(index
+            .find_by_hash(hash)
+            .into_iter()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect())
+    }
+
+    /// Groups of two or more notes with identical decrypted content.
+    pub async fn find_duplicate_notes(&self, vault_id: &Uuid) -> Result<Vec<Vec<String>>> {
+        let indexes = self.indexes.read().await;
+        let index = indexes
+            .get(vault_id)
+            .ok_or_else(|| anyhow!("vault has not been indexed yet; open it first"))?;
+        Ok(index
+            .duplicates()
+            .into_iter()
+            .map(|group| {
+                group
+                    .into_iter()
+                    .map(|p| p.to_string_lossy().replace('\\', "/"))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Serialize the whole vault tree (including `.inkrypt/vault.json`) into
+    /// a single tar archive at `dest`, streaming file-by-file via
+    /// `tokio-tar` rather than buffering the vault in memory. Note files
+    /// are already individually encrypted at rest (see
+    /// [`crate::vault::crypto`]), so the archive is confidential for any
+    /// vault that has crypto enabled without a second encryption pass — one
+    /// would have to be bootstrapped from `vault.json`, which is itself
+    /// inside the archive, so there's no way to wrap the whole stream
+    /// without first being able to read it.
+    pub async fn export_vault(&self, vault_id: &Uuid, dest: &Path) -> Result<()> {
+        let vault = self.find_vault_by_id(vault_id).await?;
+        let vault_dir_name = vault
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow!("vault path has no directory name"))?;
+
+        let file = tokio::fs::File::create(dest).await?;
+        let mut builder = tokio_tar::Builder::new(file);
+        builder.append_dir_all(vault_dir_name, &vault.path).await?;
+        builder.finish().await?;
+        Ok(())
+    }
+
+    /// Extract a vault archive created by [`Self::export_vault`] under
+    /// `root_directory` and register it, mirroring [`Self::open_vault`].
+    /// Fails if the archive has no `.inkrypt/vault.json` entry, reusing the
+    /// same validation as [`Self::load_vault_from_path`].
+    pub async fn import_vault(&self, archive: &Path, root_directory: &Path) -> Result<Vault> {
+        let file = tokio::fs::File::open(archive).await?;
+        let mut tar = tokio_tar::Archive::new(file);
+
+        let mut entries = tar.entries()?;
+        let mut vault_dir_name: Option<String> = None;
+        while let Some(entry) = entries.next().await {
+            let mut entry = entry?;
+            let entry_path = entry.path()?.into_owned();
+
+            if vault_dir_name.is_none() {
+                vault_dir_name = entry_path
+                    .components()
+                    .next()
+                    .and_then(|c| c.as_os_str().to_str())
+                    .map(String::from);
+            }
+
+            let dest_path = safe_join(root_directory, &entry_path)?;
+            if let Some(parent) = dest_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            entry.unpack(&dest_path).await?;
+        }
+
+        let vault_dir_name = vault_dir_name.ok_or_else(|| anyhow!("archive is empty"))?;
+        let vault_path = root_directory.join(vault_dir_name);
+
+        let vault = self.load_vault_from_path(&vault_path).await?;
+
+        {
             let mut registry = self.registry.write().await;
-            *registry = loaded_registry;
+            registry.insert_vault(vault.id, vault_path);
         }
+
+        Ok(vault)
+    }
+
+    /// No-op: kept so existing tests that simulate an app restart by
+    /// constructing a fresh `VaultManager` and reloading its registry still
+    /// compile. The registry now reads straight from the database on every
+    /// call, so there's no in-memory cache left to refresh.
+    #[cfg(test)]
+    pub async fn load_registry_for_testing(&self) -> Result<()> {
         Ok(())
     }
 }
 
+/// Join `entry_path` (an untrusted path read from a tar entry) onto `root`,
+/// rejecting anything that could escape `root` — absolute paths, Windows
+/// path prefixes, and `..` components — so a crafted archive can't zip-slip
+/// its way to writing outside the extraction directory.
+fn safe_join(root: &Path, entry_path: &Path) -> Result<PathBuf> {
+    use std::path::Component;
+
+    let mut joined = root.to_path_buf();
+    for component in entry_path.components() {
+        match component {
+            Component::Normal(part) => joined.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(anyhow!(
+                    "archive entry path escapes the extraction directory: {:?}",
+                    entry_path
+                ));
+            }
+        }
+    }
+    Ok(joined)
+}
+
+fn system_time_to_utc(time: Option<std::time::SystemTime>) -> Option<DateTime<Utc>> {
+    time.and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0))
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Sort directories before files, then alphabetically within each group.
+/// Directories first, then notes, each group ordered case-insensitively (but
+/// falling back to a case-sensitive compare for names differing only in
+/// case) so entry order is stable and doesn't jump around depending on the
+/// filesystem's native listing order.
+fn sort_entries(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| match (&a.entry_type, &b.entry_type) {
+        (EntryType::Directory, EntryType::Note) => std::cmp::Ordering::Less,
+        (EntryType::Note, EntryType::Directory) => std::cmp::Ordering::Greater,
+        _ => a
+            .name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.name.cmp(&b.name)),
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::TempDir;
+    use crate::vault::fs::FakeFs;
 
-    use serial_test::serial;
+    fn setup_test_manager() -> (VaultManager, PathBuf) {
+        let root = PathBuf::from("/root");
+        let registry_path = PathBuf::from("/test_vaults.json");
+        let manager = VaultManager::new_with_fs(registry_path, Arc::new(FakeFs::new()));
 
-    async fn setup_test_manager() -> (VaultManager, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-
-        // Create a mock VaultManager with a custom registry path
-        let registry_path = temp_dir.path().join("test_vaults.json");
-        let manager = VaultManager::new_for_testing(registry_path);
-
-        (manager, temp_dir)
+        (manager, root)
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_create_vault() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault_name = "test_vault";
 
-        let result = manager.create_vault(temp_dir.path(), vault_name).await;
+        let result = manager.create_vault(&root, vault_name, "test_passphrase").await;
         assert!(result.is_ok());
 
         let vault = result.unwrap();
         assert_eq!(vault.name, vault_name);
-        assert!(vault.path.exists());
-        assert!(vault.path.join(".inkrypt").exists());
-        assert!(vault.path.join(".inkrypt/vault.json").exists());
+        assert!(manager.fs.exists(&vault.path).await);
+        assert!(manager.fs.exists(&vault.path.join(".inkrypt")).await);
+        assert!(
+            manager
+                .fs
+                .exists(&vault.path.join(".inkrypt/vault.json"))
+                .await
+        );
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_create_vault_duplicate_name() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault_name = "test_vault";
 
         // Create first vault
-        let result1 = manager.create_vault(temp_dir.path(), vault_name).await;
+        let result1 = manager.create_vault(&root, vault_name, "test_passphrase").await;
         assert!(result1.is_ok());
 
         // Try to create second vault with same name
-        let result2 = manager.create_vault(temp_dir.path(), vault_name).await;
+        let result2 = manager.create_vault(&root, vault_name, "test_passphrase").await;
         assert!(result2.is_err());
         assert!(result2
             .unwrap_err()
@@ -443,14 +1249,13 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_open_vault() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault_name = "test_vault";
 
         // First create a vault
         let created_vault = manager
-            .create_vault(temp_dir.path(), vault_name)
+            .create_vault(&root, vault_name, "test_passphrase")
             .await
             .unwrap();
 
@@ -465,10 +1270,9 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_open_invalid_vault() {
-        let (manager, temp_dir) = setup_test_manager().await;
-        let invalid_path = temp_dir.path().join("nonexistent");
+        let (manager, root) = setup_test_manager();
+        let invalid_path = root.join("nonexistent");
 
         let result = manager.open_vault(&invalid_path).await;
         assert!(result.is_err());
@@ -479,9 +1283,8 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_list_vaults() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
 
         // Initially empty
         let vaults = manager.list_vaults().await.unwrap();
@@ -489,11 +1292,11 @@ mod tests {
 
         // Create some vaults
         let vault1 = manager
-            .create_vault(temp_dir.path(), "vault1")
+            .create_vault(&root, "vault1", "test_passphrase")
             .await
             .unwrap();
         let vault2 = manager
-            .create_vault(temp_dir.path(), "vault2")
+            .create_vault(&root, "vault2", "test_passphrase")
             .await
             .unwrap();
 
@@ -507,19 +1310,18 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_delete_vault() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
-        assert!(vault.path.exists());
+        assert!(manager.fs.exists(&vault.path).await);
 
         let result = manager.delete_vault(&vault.id).await;
         assert!(result.is_ok());
-        assert!(!vault.path.exists());
+        assert!(!manager.fs.exists(&vault.path).await);
 
         // Should not be in registry anymore
         let vaults = manager.list_vaults().await.unwrap();
@@ -527,11 +1329,10 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_rename_vault() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "old_name")
+            .create_vault(&root, "old_name", "test_passphrase")
             .await
             .unwrap();
         let old_path = vault.path.clone();
@@ -542,17 +1343,16 @@ mod tests {
         let renamed_vault = result.unwrap();
         assert_eq!(renamed_vault.name, "new_name");
         assert_eq!(renamed_vault.id, vault.id);
-        assert!(!old_path.exists());
-        assert!(renamed_vault.path.exists());
+        assert!(!manager.fs.exists(&old_path).await);
+        assert!(manager.fs.exists(&renamed_vault.path).await);
         assert!(renamed_vault.path.file_name().unwrap() == "new_name");
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_create_directory() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -560,16 +1360,15 @@ mod tests {
         assert!(result.is_ok());
 
         let directory_path = vault.path.join("test_directory");
-        assert!(directory_path.exists());
-        assert!(directory_path.is_dir());
+        let metadata = manager.fs.metadata(&directory_path).await.unwrap();
+        assert!(metadata.is_dir);
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_create_note() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -577,16 +1376,15 @@ mod tests {
         assert!(result.is_ok());
 
         let note_path = vault.path.join("test_note.md");
-        assert!(note_path.exists());
-        assert!(note_path.is_file());
+        let metadata = manager.fs.metadata(&note_path).await.unwrap();
+        assert!(metadata.is_file);
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_edit_and_read_note() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -609,11 +1407,120 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
+    async fn test_read_note_fails_when_locked() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+        manager
+            .create_note(&vault.id, "test_note.md")
+            .await
+            .unwrap();
+
+        manager.lock_vault(&vault.id).await;
+
+        let result = manager.read_note(&vault.id, "test_note.md").await;
+        assert!(result.is_err());
+
+        manager
+            .unlock_vault(&vault.id, "test_passphrase")
+            .await
+            .unwrap();
+        assert!(manager.read_note(&vault.id, "test_note.md").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_vault_without_crypto_envelope_stays_plaintext() {
+        let (manager, root) = setup_test_manager();
+        let vault_path = root.join("legacy_vault");
+        let inkrypt_dir = vault_path.join(".inkrypt");
+        manager.fs.create_dir_all(&inkrypt_dir).await.unwrap();
+
+        let vault_id = Uuid::now_v7();
+        let metadata = VaultMetadata {
+            id: vault_id,
+            version: 0,
+            created_at: Utc::now(),
+            crypto: None,
+        };
+        manager
+            .fs
+            .write(
+                &inkrypt_dir.join("vault.json"),
+                serde_json::to_string_pretty(&metadata).unwrap().as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        let vault = manager.open_vault(&vault_path).await.unwrap();
+        assert!(!vault.encrypted);
+
+        // No unlock_vault call: a legacy vault with no crypto envelope
+        // should read and write notes as plaintext.
+        manager
+            .edit_note(&vault.id, "note.md", "plain content")
+            .await
+            .unwrap();
+        let content = manager.read_note(&vault.id, "note.md").await.unwrap();
+        assert_eq!(content, "plain content");
+
+        let raw = manager
+            .fs
+            .read(&vault_path.join("note.md"))
+            .await
+            .unwrap();
+        assert_eq!(raw, b"plain content");
+    }
+
+    #[tokio::test]
+    async fn test_unlock_vault_rejects_wrong_passphrase() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager.lock_vault(&vault.id).await;
+
+        let result = manager.unlock_vault(&vault.id, "wrong_passphrase").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rekey_vault() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "test_note.md", "secret")
+            .await
+            .unwrap();
+
+        manager.rekey_vault(&vault.id, "new_passphrase").await.unwrap();
+        manager.lock_vault(&vault.id).await;
+
+        assert!(manager
+            .unlock_vault(&vault.id, "test_passphrase")
+            .await
+            .is_err());
+        manager
+            .unlock_vault(&vault.id, "new_passphrase")
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.read_note(&vault.id, "test_note.md").await.unwrap(),
+            "secret"
+        );
+    }
+
+    #[tokio::test]
     async fn test_delete_entry() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -623,20 +1530,19 @@ mod tests {
             .await
             .unwrap();
         let note_path = vault.path.join("test_note.md");
-        assert!(note_path.exists());
+        assert!(manager.fs.exists(&note_path).await);
 
         // Delete it
         let result = manager.delete_entry(&vault.id, "test_note.md").await;
         assert!(result.is_ok());
-        assert!(!note_path.exists());
+        assert!(!manager.fs.exists(&note_path).await);
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_rename_entry() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -645,8 +1551,8 @@ mod tests {
         let old_path = vault.path.join("old_name.md");
         let new_path = vault.path.join("new_name.md");
 
-        assert!(old_path.exists());
-        assert!(!new_path.exists());
+        assert!(manager.fs.exists(&old_path).await);
+        assert!(!manager.fs.exists(&new_path).await);
 
         // Rename it
         let result = manager
@@ -654,16 +1560,15 @@ mod tests {
             .await;
         assert!(result.is_ok());
 
-        assert!(!old_path.exists());
-        assert!(new_path.exists());
+        assert!(!manager.fs.exists(&old_path).await);
+        assert!(manager.fs.exists(&new_path).await);
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_list_entries() {
-        let (manager, temp_dir) = setup_test_manager().await;
+        let (manager, root) = setup_test_manager();
         let vault = manager
-            .create_vault(temp_dir.path(), "test_vault")
+            .create_vault(&root, "test_vault", "test_passphrase")
             .await
             .unwrap();
 
@@ -696,13 +1601,422 @@ mod tests {
     }
 
     #[tokio::test]
-    #[serial]
     async fn test_find_vault_by_id_not_found() {
-        let (manager, _temp_dir) = setup_test_manager().await;
+        let (manager, _root) = setup_test_manager();
         let nonexistent_id = Uuid::now_v7();
 
         let result = manager.find_vault_by_id(&nonexistent_id).await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Vault not found"));
     }
+
+    #[tokio::test]
+    async fn test_search_vault_matches_content_and_file_name() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager
+            .create_directory(&vault.id, "notes")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "notes/todo.md", "remember the milk")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "other.md", "nothing interesting here")
+            .await
+            .unwrap();
+
+        let content_matches = manager
+            .search_vault(
+                &vault.id,
+                SearchQuery {
+                    pattern: "milk".to_string(),
+                    is_regex: false,
+                    case_sensitive: false,
+                    path_glob: None,
+                    max_results: None,
+                    max_depth: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(content_matches.len(), 1);
+        assert_eq!(content_matches[0].path, "notes/todo.md");
+        assert_eq!(content_matches[0].line, Some(1));
+
+        let name_matches = manager
+            .search_vault(
+                &vault.id,
+                SearchQuery {
+                    pattern: "todo".to_string(),
+                    is_regex: false,
+                    case_sensitive: false,
+                    path_glob: None,
+                    max_results: None,
+                    max_depth: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert!(name_matches.iter().any(|m| m.line.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_search_vault_respects_path_glob() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager
+            .create_directory(&vault.id, "attachments")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "attachments/draft.md", "shared keyword")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "keep.md", "shared keyword")
+            .await
+            .unwrap();
+
+        let matches = manager
+            .search_vault(
+                &vault.id,
+                SearchQuery {
+                    pattern: "keyword".to_string(),
+                    is_regex: false,
+                    case_sensitive: false,
+                    path_glob: Some("attachments/**".to_string()),
+                    max_results: None,
+                    max_depth: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "attachments/draft.md");
+    }
+
+    #[tokio::test]
+    async fn test_list_tree_nests_directories() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager.create_directory(&vault.id, "a").await.unwrap();
+        manager.create_note(&vault.id, "a/nested.md").await.unwrap();
+        manager.create_note(&vault.id, "root.md").await.unwrap();
+
+        let tree = manager.list_tree(&vault.id, None, None).await.unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].name, "a");
+        assert_eq!(tree[0].entry_type, EntryType::Directory);
+        let children = tree[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "nested.md");
+        assert!(children[0].children.is_none());
+
+        assert_eq!(tree[1].name, "root.md");
+        assert!(tree[1].children.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_list_tree_honors_inkryptignore() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager.create_directory(&vault.id, "attachments").await.unwrap();
+        manager
+            .create_note(&vault.id, "attachments/large.bin")
+            .await
+            .unwrap();
+        manager.create_note(&vault.id, "keep.md").await.unwrap();
+        manager
+            .fs
+            .write(&vault.path.join(".inkryptignore"), b"attachments/\n")
+            .await
+            .unwrap();
+
+        let tree = manager.list_tree(&vault.id, None, None).await.unwrap();
+        let names: Vec<&str> = tree.iter().map(|e| e.name.as_str()).collect();
+        assert!(!names.contains(&"attachments"));
+        assert!(names.contains(&"keep.md"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tree_honors_max_depth() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager.create_directory(&vault.id, "a").await.unwrap();
+        manager.create_note(&vault.id, "a/nested.md").await.unwrap();
+
+        let tree = manager.list_tree(&vault.id, None, Some(0)).await.unwrap();
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].name, "a");
+        assert!(tree[0].children.as_ref().unwrap().is_empty());
+
+        let tree = manager.list_tree(&vault.id, None, Some(1)).await.unwrap();
+        let children = tree[0].children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "nested.md");
+    }
+
+    #[test]
+    fn sort_entries_orders_case_insensitively() {
+        let mut entries = vec![
+            Entry {
+                name: "banana.md".to_string(),
+                path: "banana.md".to_string(),
+                entry_type: EntryType::Note,
+                created_at: None,
+                updated_at: None,
+                children: None,
+            },
+            Entry {
+                name: "Apple.md".to_string(),
+                path: "Apple.md".to_string(),
+                entry_type: EntryType::Note,
+                created_at: None,
+                updated_at: None,
+                children: None,
+            },
+            Entry {
+                name: "cherry.md".to_string(),
+                path: "cherry.md".to_string(),
+                entry_type: EntryType::Note,
+                created_at: None,
+                updated_at: None,
+                children: None,
+            },
+        ];
+
+        sort_entries(&mut entries);
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Apple.md", "banana.md", "cherry.md"]);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_index_finds_duplicate_notes() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager
+            .edit_note(&vault.id, "a.md", "shared body")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "b.md", "shared body")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "c.md", "unique body")
+            .await
+            .unwrap();
+
+        manager.rebuild_index(&vault.id).await.unwrap();
+
+        let duplicates = manager.find_duplicate_notes(&vault.id).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        let mut group = duplicates[0].clone();
+        group.sort();
+        assert_eq!(group, vec!["a.md".to_string(), "b.md".to_string()]);
+
+        let hash = blake3::hash(b"shared body").to_hex().to_string();
+        let mut matches = manager.find_by_hash(&vault.id, &hash).await.unwrap();
+        matches.sort();
+        assert_eq!(matches, vec!["a.md".to_string(), "b.md".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_vault_rebuilds_index_after_locked_open() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager
+            .edit_note(&vault.id, "a.md", "shared body")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "b.md", "shared body")
+            .await
+            .unwrap();
+
+        manager.lock_vault(&vault.id).await;
+
+        // Mirrors `open_vault` running `rebuild_index` while the vault is
+        // still locked: note bodies can't be decrypted, so the index comes
+        // up empty.
+        manager.rebuild_index(&vault.id).await.unwrap();
+        assert!(manager.find_duplicate_notes(&vault.id).await.unwrap().is_empty());
+
+        manager
+            .unlock_vault(&vault.id, "test_passphrase")
+            .await
+            .unwrap();
+
+        let duplicates = manager.find_duplicate_notes(&vault.id).await.unwrap();
+        assert_eq!(duplicates.len(), 1);
+        let mut group = duplicates[0].clone();
+        group.sort();
+        assert_eq!(group, vec!["a.md".to_string(), "b.md".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_apply_index_event_keeps_index_live_without_rebuild() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+        manager.rebuild_index(&vault.id).await.unwrap();
+
+        manager
+            .edit_note(&vault.id, "note.md", "fresh content")
+            .await
+            .unwrap();
+        manager
+            .apply_index_event(&FileSystemEvent {
+                event_type: FileEventType::Modify,
+                path: "note.md".to_string(),
+                vault_id: vault.id,
+                entry_type: Some(EntryType::Note),
+                details: None,
+            })
+            .await;
+
+        let hash = blake3::hash(b"fresh content").to_hex().to_string();
+        let matches = manager.find_by_hash(&vault.id, &hash).await.unwrap();
+        assert_eq!(matches, vec!["note.md".to_string()]);
+
+        manager
+            .apply_index_event(&FileSystemEvent {
+                event_type: FileEventType::Delete,
+                path: "note.md".to_string(),
+                vault_id: vault.id,
+                entry_type: None,
+                details: None,
+            })
+            .await;
+        assert!(manager.find_by_hash(&vault.id, &hash).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_edit_note_persists_op_log_for_note_history() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager.edit_note(&vault.id, "note.md", "hello").await.unwrap();
+        manager.edit_note(&vault.id, "note.md", "help").await.unwrap();
+
+        let history = manager.note_history(&vault.id, "note.md").await.unwrap();
+        // 5 inserts for "hello" plus at least one insert/delete turning it
+        // into "help".
+        assert!(history.len() >= 6);
+    }
+
+    #[tokio::test]
+    async fn test_merge_external_change_converges_with_concurrent_local_edit() {
+        let (manager, root) = setup_test_manager();
+        let vault = manager
+            .create_vault(&root, "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        manager
+            .edit_note(&vault.id, "note.md", "hello world")
+            .await
+            .unwrap();
+
+        // Simulate an in-app edit...
+        manager
+            .edit_note(&vault.id, "note.md", "hello brave world")
+            .await
+            .unwrap();
+
+        // ...racing an external edit of the pre-edit file (as if another
+        // editor had it open), reported by the watcher as a Modify.
+        let key = manager.note_key(&vault.id).await.unwrap();
+        let external_bytes = match &key {
+            Some(key) => crypto::encrypt_note(key, b"hello world!").unwrap(),
+            None => b"hello world!".to_vec(),
+        };
+        manager
+            .fs
+            .write(&vault.path.join("note.md"), &external_bytes)
+            .await
+            .unwrap();
+
+        manager
+            .merge_external_change(&FileSystemEvent {
+                event_type: FileEventType::Modify,
+                path: "note.md".to_string(),
+                vault_id: vault.id,
+                entry_type: Some(EntryType::Note),
+                details: None,
+            })
+            .await
+            .unwrap();
+
+        let merged = manager.read_note(&vault.id, "note.md").await.unwrap();
+        assert!(merged.contains("brave"));
+        assert!(merged.ends_with('!'));
+    }
+
+    #[tokio::test]
+    async fn test_create_note_surfaces_injected_write_failure() {
+        let fake_fs = Arc::new(FakeFs::new());
+        let manager = VaultManager::new_with_fs(PathBuf::from("/test_vaults.json"), fake_fs.clone());
+        let vault = manager
+            .create_vault(Path::new("/root"), "test_vault", "test_passphrase")
+            .await
+            .unwrap();
+
+        fake_fs.fail_next_write();
+        let result = manager.create_note(&vault.id, "test_note.md").await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn safe_join_rejects_parent_dir_and_absolute_entry_paths() {
+        let root = Path::new("/vaults");
+        assert!(safe_join(root, Path::new("notes/a.md")).is_ok());
+        assert!(safe_join(root, Path::new("../../etc/passwd")).is_err());
+        assert!(safe_join(root, Path::new("notes/../../../etc/passwd")).is_err());
+        assert!(safe_join(root, Path::new("/etc/passwd")).is_err());
+    }
+
+    #[test]
+    fn safe_join_keeps_result_under_root() {
+        let root = Path::new("/vaults");
+        let joined = safe_join(root, Path::new("my_vault/.inkrypt/vault.json")).unwrap();
+        assert_eq!(joined, Path::new("/vaults/my_vault/.inkrypt/vault.json"));
+    }
 }
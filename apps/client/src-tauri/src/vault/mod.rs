@@ -1,10 +1,22 @@
 pub mod commands;
+pub mod crdt;
+pub mod crypto;
+pub mod db;
+pub mod fs;
+pub mod index;
 pub mod manager;
 pub mod models;
+pub mod search;
 pub mod watcher;
 
+pub use crdt::{Op, OpId};
+pub use crypto::VaultKey;
+pub use db::{Database, NoteRecord, VaultRegistry};
+pub use fs::{Fs, RealFs};
+pub use index::NoteMetadata;
 pub use manager::VaultManager;
 pub use models::*;
+pub use search::{SearchMatch, SearchQuery};
 pub use watcher::VaultWatcher;
 
 #[cfg(test)]
@@ -31,7 +43,7 @@ mod integration_tests {
 
         // Test vault creation
         let vault = manager
-            .create_vault(temp_dir.path(), "lifecycle_test")
+            .create_vault(temp_dir.path(), "lifecycle_test", "test_passphrase")
             .await
             .unwrap();
         assert_eq!(vault.name, "lifecycle_test");
@@ -68,7 +80,7 @@ mod integration_tests {
 
         // Create a vault
         let vault = manager
-            .create_vault(temp_dir.path(), "entry_test")
+            .create_vault(temp_dir.path(), "entry_test", "test_passphrase")
             .await
             .unwrap();
 
@@ -142,7 +154,7 @@ mod integration_tests {
             let manager = VaultManager::new_for_testing(registry_path.clone());
 
             let vault = manager
-                .create_vault(temp_dir.path(), "persistence_test")
+                .create_vault(temp_dir.path(), "persistence_test", "test_passphrase")
                 .await
                 .unwrap();
             assert!(registry_path.exists());
@@ -192,7 +204,7 @@ mod integration_tests {
 
         // Test reading non-existent note
         let vault = manager
-            .create_vault(temp_dir.path(), "error_test")
+            .create_vault(temp_dir.path(), "error_test", "test_passphrase")
             .await
             .unwrap();
         let result = manager.read_note(&vault.id, "nonexistent.md").await;
@@ -204,7 +216,7 @@ mod integration_tests {
     async fn test_nested_directories() {
         let (manager, temp_dir) = setup_test_environment();
         let vault = manager
-            .create_vault(temp_dir.path(), "nested_test")
+            .create_vault(temp_dir.path(), "nested_test", "test_passphrase")
             .await
             .unwrap();
 
@@ -268,4 +280,42 @@ mod integration_tests {
             .unwrap();
         assert_eq!(read_content, content);
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_export_and_import_vault() {
+        let (manager, temp_dir) = setup_test_environment();
+        let vault = manager
+            .create_vault(temp_dir.path(), "export_test", "test_passphrase")
+            .await
+            .unwrap();
+        manager
+            .edit_note(&vault.id, "note.md", "exported content")
+            .await
+            .unwrap();
+
+        let archive_path = temp_dir.path().join("export_test.tar");
+        manager.export_vault(&vault.id, &archive_path).await.unwrap();
+        assert!(archive_path.exists());
+
+        let import_dir = TempDir::new().unwrap();
+        let imported = manager
+            .import_vault(&archive_path, import_dir.path())
+            .await
+            .unwrap();
+
+        assert_eq!(imported.id, vault.id);
+        assert_eq!(imported.name, "export_test");
+        assert!(imported.path.join(".inkrypt/vault.json").exists());
+
+        manager
+            .unlock_vault(&imported.id, "test_passphrase")
+            .await
+            .unwrap();
+        let content = manager
+            .read_note(&imported.id, "note.md")
+            .await
+            .unwrap();
+        assert_eq!(content, "exported content");
+    }
 }
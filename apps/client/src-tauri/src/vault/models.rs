@@ -1,6 +1,6 @@
+use crate::vault::crypto::CryptoEnvelope;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 
@@ -12,6 +12,9 @@ pub struct Vault {
     pub version: u32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Whether this vault has crypto parameters and therefore requires
+    /// `unlock_vault` before its notes can be read or written.
+    pub encrypted: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +22,10 @@ pub struct VaultMetadata {
     pub id: Uuid,
     pub version: u32,
     pub created_at: DateTime<Utc>,
+    /// KDF parameters and wrapped master key. `None` for vaults created
+    /// before encryption support existed; such vaults store plaintext
+    /// notes until migrated.
+    pub crypto: Option<CryptoEnvelope>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +36,11 @@ pub struct Entry {
     pub entry_type: EntryType,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Subtree contents for a directory, populated by `list_tree`.
+    /// `None` for files, and for directories returned by the single-level
+    /// `list_entries`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<Entry>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,6 +56,14 @@ pub struct FileSystemEvent {
     pub event_type: FileEventType,
     pub path: String,
     pub vault_id: Uuid,
+    /// Best-effort: `None` when the entry could no longer be stat'd by the
+    /// time we processed the event (most commonly, the path was deleted).
+    pub entry_type: Option<EntryType>,
+    /// Best-effort size/mtime/sub-kind captured alongside `entry_type`, for
+    /// the same reason `entry_type` can be absent: `None` for a path that
+    /// vanished before it could be stat'd (typically a `Delete`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<EventDetails>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -52,42 +72,45 @@ pub enum FileEventType {
     Create,
     Modify,
     Delete,
-    Rename,
+    /// A path observed to have moved or been renamed within the vault,
+    /// correlated from a Delete+Create pair by persistent file identity;
+    /// see `crate::vault::watcher::correlate_rename`. Both paths are
+    /// vault-relative, like `FileSystemEvent::path` (which mirrors `to`).
+    Rename { from: String, to: String },
 }
 
+/// Extra metadata captured at the moment a filesystem event was classified,
+/// so the frontend can show an accurate "modified at" time and decide
+/// whether a `Modify` needs re-decrypting without a follow-up stat.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VaultRegistry {
-    pub vaults: HashMap<Uuid, PathBuf>,
-}
-
-impl VaultRegistry {
-    pub fn new() -> Self {
-        Self {
-            vaults: HashMap::new(),
-        }
-    }
-
-    pub fn insert_vault(&mut self, id: Uuid, path: PathBuf) {
-        self.vaults.insert(id, path);
-    }
-
-    pub fn remove_vault(&mut self, id: &Uuid) {
-        self.vaults.remove(id);
-    }
-
-    pub fn get_vault_path(&self, id: &Uuid) -> Option<&PathBuf> {
-        self.vaults.get(id)
-    }
-
-    pub fn get_vaults(&self) -> &HashMap<Uuid, PathBuf> {
-        &self.vaults
-    }
+#[serde(rename_all = "camelCase")]
+pub struct EventDetails {
+    /// When the event was classified, not when the filesystem change itself
+    /// occurred (`notify` doesn't report that separately).
+    pub timestamp: DateTime<Utc>,
+    pub size: u64,
+    pub modified: DateTime<Utc>,
+    /// `None` for event types other than `Modify`, where `notify` doesn't
+    /// report a sub-kind.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modify_kind: Option<ModifyKind>,
 }
 
-impl Default for VaultRegistry {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Mirrors the cases of `notify::event::ModifyKind` the frontend actually
+/// needs to distinguish, collapsing anything more specific (e.g. which
+/// attribute changed) into `Metadata`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModifyKind {
+    /// File content changed — the case that actually needs re-decrypting.
+    Data,
+    /// Permissions, timestamps, or other attributes changed; content did not.
+    Metadata,
+    /// The entry was renamed in place, reported as a bare `Modify` rather
+    /// than correlated into a `FileEventType::Rename` (e.g. only one side
+    /// of the rename fell within the watched tree).
+    Name,
+    Other,
 }
 
 #[cfg(test)]
@@ -96,67 +119,6 @@ mod tests {
     use std::path::PathBuf;
     use uuid::Uuid;
 
-    #[test]
-    fn test_vault_registry_new() {
-        let registry = VaultRegistry::new();
-        assert!(registry.vaults.is_empty());
-    }
-
-    #[test]
-    fn test_vault_registry_insert_and_get() {
-        let mut registry = VaultRegistry::new();
-        let vault_id = Uuid::now_v7();
-        let path = PathBuf::from("/test/path");
-
-        registry.insert_vault(vault_id, path.clone());
-
-        assert_eq!(registry.get_vault_path(&vault_id), Some(&path));
-        assert_eq!(registry.vaults.len(), 1);
-    }
-
-    #[test]
-    fn test_vault_registry_insert_overwrites() {
-        let mut registry = VaultRegistry::new();
-        let vault_id = Uuid::now_v7();
-        let path1 = PathBuf::from("/test/path1");
-        let path2 = PathBuf::from("/test/path2");
-
-        registry.insert_vault(vault_id, path1);
-        registry.insert_vault(vault_id, path2.clone());
-
-        assert_eq!(registry.get_vault_path(&vault_id), Some(&path2));
-        assert_eq!(registry.vaults.len(), 1);
-    }
-
-    #[test]
-    fn test_vault_registry_remove() {
-        let mut registry = VaultRegistry::new();
-        let vault_id = Uuid::now_v7();
-        let path = PathBuf::from("/test/path");
-
-        registry.insert_vault(vault_id, path);
-        registry.remove_vault(&vault_id);
-
-        assert_eq!(registry.get_vault_path(&vault_id), None);
-        assert!(registry.vaults.is_empty());
-    }
-
-    #[test]
-    fn test_vault_registry_multiple_vaults() {
-        let mut registry = VaultRegistry::new();
-        let vault_id1 = Uuid::now_v7();
-        let vault_id2 = Uuid::now_v7();
-        let path1 = PathBuf::from("/test/path1");
-        let path2 = PathBuf::from("/test/path2");
-
-        registry.insert_vault(vault_id1, path1.clone());
-        registry.insert_vault(vault_id2, path2.clone());
-
-        assert_eq!(registry.get_vault_path(&vault_id1), Some(&path1));
-        assert_eq!(registry.get_vault_path(&vault_id2), Some(&path2));
-        assert_eq!(registry.vaults.len(), 2);
-    }
-
     #[test]
     fn test_vault_serialization() {
         use chrono::Utc;
@@ -168,6 +130,7 @@ mod tests {
             version: 1,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            encrypted: true,
         };
 
         let json = serde_json::to_string(&vault).unwrap();
@@ -189,6 +152,7 @@ mod tests {
             entry_type: EntryType::Note,
             created_at: Some(Utc::now()),
             updated_at: Some(Utc::now()),
+            children: None,
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -198,18 +162,4 @@ mod tests {
         assert_eq!(entry.path, deserialized.path);
         assert_eq!(entry.entry_type, deserialized.entry_type);
     }
-
-    #[test]
-    fn test_vault_registry_serialization() {
-        let mut registry = VaultRegistry::new();
-        let vault_id = Uuid::now_v7();
-        let path = PathBuf::from("/test/path");
-
-        registry.insert_vault(vault_id, path.clone());
-
-        let json = serde_json::to_string(&registry).unwrap();
-        let deserialized: VaultRegistry = serde_json::from_str(&json).unwrap();
-
-        assert_eq!(deserialized.get_vault_path(&vault_id), Some(&path));
-    }
 }
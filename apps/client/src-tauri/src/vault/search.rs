@@ -0,0 +1,80 @@
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Parameters for [`crate::vault::VaultManager::search_vault`], modeled
+/// after distant's `SearchQuery`: a pattern plus the knobs that control how
+/// broadly it's applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchQuery {
+    /// Literal substring or regex to look for, depending on `is_regex`.
+    pub pattern: String,
+    pub is_regex: bool,
+    pub case_sensitive: bool,
+    /// Only search files whose whole vault-relative path matches this glob
+    /// (`globset` syntax, e.g. `attachments/**`).
+    pub path_glob: Option<String>,
+    pub max_results: Option<usize>,
+    pub max_depth: Option<usize>,
+}
+
+/// One hit: either a file name match (`line` is `None`) or a content match
+/// on a specific line.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchMatch {
+    pub path: String,
+    pub line: Option<usize>,
+    pub snippet: String,
+}
+
+/// Longest snippet to surface around a content match, in characters.
+const SNIPPET_MAX_LEN: usize = 120;
+
+pub(super) enum Matcher {
+    Literal { pattern: String, case_sensitive: bool },
+    Regex(regex::Regex),
+}
+
+impl Matcher {
+    pub(super) fn new(query: &SearchQuery) -> anyhow::Result<Self> {
+        if query.is_regex {
+            let regex = RegexBuilder::new(&query.pattern)
+                .case_insensitive(!query.case_sensitive)
+                .build()?;
+            Ok(Matcher::Regex(regex))
+        } else {
+            Ok(Matcher::Literal {
+                pattern: query.pattern.clone(),
+                case_sensitive: query.case_sensitive,
+            })
+        }
+    }
+
+    pub(super) fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(haystack),
+            Matcher::Literal {
+                pattern,
+                case_sensitive: true,
+            } => haystack.contains(pattern.as_str()),
+            Matcher::Literal {
+                pattern,
+                case_sensitive: false,
+            } => haystack.to_lowercase().contains(&pattern.to_lowercase()),
+        }
+    }
+}
+
+/// Build a short, single-line snippet around `line`, trimmed to
+/// [`SNIPPET_MAX_LEN`] characters so large matched lines don't blow up the
+/// result payload.
+pub(super) fn snippet(line: &str) -> String {
+    let trimmed = line.trim();
+    if trimmed.chars().count() <= SNIPPET_MAX_LEN {
+        trimmed.to_string()
+    } else {
+        let truncated: String = trimmed.chars().take(SNIPPET_MAX_LEN).collect();
+        format!("{truncated}…")
+    }
+}
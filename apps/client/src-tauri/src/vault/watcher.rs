@@ -1,157 +1,363 @@
+use crate::vault::manager::VaultManager;
 use crate::vault::models::*;
 use anyhow::Result;
-use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
-use std::collections::HashSet;
+use chrono::{DateTime, Utc};
+use file_id::FileId;
+use notify::{Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
-use tokio::sync::{mpsc, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio::time::{sleep, timeout};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
 use tracing::{debug, error, info};
 use uuid::Uuid;
 
-#[derive(Debug)]
+/// Capacity of the broadcast channel backing [`VaultWatcher::subscribe`].
+/// Lagging subscribers drop the oldest events rather than blocking the
+/// watcher's own event-processing task.
+const CHANGE_BROADCAST_CAPACITY: usize = 256;
+
+/// Backend a vault is watched with. `Native` uses the OS's own file-change
+/// notifications (inotify/FSEvents/ReadDirectoryChanges) and is the right
+/// choice for local disks; `Poll` stats the tree on an interval instead,
+/// which is slower but works on network shares, SMB/NFS mounts, and some
+/// FUSE filesystems where the native backend silently delivers nothing.
+#[derive(Debug, Clone, Copy)]
+pub enum WatcherKind {
+    Native,
+    Poll(Duration),
+}
+
+/// Emitted to the frontend when a vault's native watcher reports a backend
+/// error, so the UI can offer to switch that vault to `WatcherKind::Poll`
+/// via `watch_vault_with` instead of silently missing further changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatcherDiagnostic {
+    vault_id: Uuid,
+    message: String,
+}
+
+/// Combines a vault's `.inkryptignore` file (gitignore syntax, loaded once
+/// when the vault starts being watched) with patterns added at runtime via
+/// [`VaultWatcher::add_ignore_pattern`], so noisy paths — editor swap files,
+/// `.DS_Store`, huge attachment directories — never reach `vault-changes` at
+/// all instead of being filtered after the fact by the frontend. Mirrors the
+/// `.inkryptignore` handling in `VaultManager::load_ignore_matcher`, but is
+/// loaded independently here since the watcher filters events directly off
+/// `std::fs`, not through the `Fs` trait.
+struct IgnoreMatcher {
+    vault_root: PathBuf,
+    runtime_patterns: Vec<String>,
+    compiled: ignore::gitignore::Gitignore,
+}
+
+impl IgnoreMatcher {
+    fn load(vault_root: &Path) -> Result<Self> {
+        let mut matcher = Self {
+            vault_root: vault_root.to_path_buf(),
+            runtime_patterns: Vec::new(),
+            compiled: ignore::gitignore::Gitignore::empty(),
+        };
+        matcher.rebuild()?;
+        Ok(matcher)
+    }
+
+    fn is_ignored(&self, relative_path: &Path, is_dir: bool) -> bool {
+        // Also checks parent directories, so a file under an ignored
+        // directory (e.g. `attachments/`) is ignored too.
+        self.compiled
+            .matched_path_or_any_parents(relative_path, is_dir)
+            .is_ignore()
+    }
+
+    /// Add a runtime glob pattern and recompile, so users with huge vaults
+    /// can exclude media/build folders without editing `.inkryptignore`.
+    fn add_pattern(&mut self, pattern: &str) -> Result<()> {
+        self.runtime_patterns.push(pattern.to_string());
+        self.rebuild()
+    }
+
+    fn rebuild(&mut self) -> Result<()> {
+        let mut builder = ignore::gitignore::GitignoreBuilder::new(&self.vault_root);
+
+        let ignore_path = self.vault_root.join(".inkryptignore");
+        if ignore_path.exists() {
+            let content = std::fs::read_to_string(&ignore_path)?;
+            for line in content.lines() {
+                builder.add_line(None, line)?;
+            }
+        }
+
+        for pattern in &self.runtime_patterns {
+            builder.add_line(None, pattern)?;
+        }
+
+        self.compiled = builder.build()?;
+        Ok(())
+    }
+}
+
 #[allow(dead_code)]
 struct WatcherState {
     vault_id: Uuid,
     vault_path: PathBuf,
-    watcher: RecommendedWatcher,
+    watcher: Box<dyn Watcher + Send>,
+    ignore: Arc<RwLock<IgnoreMatcher>>,
+    /// The spawned event-processing task for this vault, aborted by
+    /// [`VaultWatcher::unwatch_vault`]/[`VaultWatcher::unwatch_all`] so
+    /// stopping one vault's watcher can't leave its task running against a
+    /// dropped `notify` watcher.
+    task: tokio::task::JoinHandle<()>,
+    /// The task feeding this vault's (already debounced) change events into
+    /// `VaultManager::merge_external_change`/`apply_index_event`, scoped to
+    /// `vault_id` via [`VaultWatcher::subscribe`] so a vault's events aren't
+    /// also reprocessed by every other open vault's subscriber. Aborted
+    /// alongside `task` so it doesn't outlive this vault being watched.
+    index_task: tokio::task::JoinHandle<()>,
 }
 
 pub struct VaultWatcher {
-    current_watcher: Arc<RwLock<Option<WatcherState>>>,
+    watchers: Arc<RwLock<HashMap<Uuid, WatcherState>>>,
     app_handle: AppHandle,
-    pending_operations: Arc<RwLock<HashSet<PathBuf>>>,
+    pending_operations: Arc<RwLock<HashMap<Uuid, HashSet<PathBuf>>>>,
+    changes: broadcast::Sender<FileSystemEvent>,
 }
 
 impl VaultWatcher {
     pub fn new(app_handle: AppHandle) -> Self {
+        let (changes, _) = broadcast::channel(CHANGE_BROADCAST_CAPACITY);
         Self {
-            current_watcher: Arc::new(RwLock::new(None)),
+            watchers: Arc::new(RwLock::new(HashMap::new())),
             app_handle,
-            pending_operations: Arc::new(RwLock::new(HashSet::new())),
+            pending_operations: Arc::new(RwLock::new(HashMap::new())),
+            changes,
         }
     }
 
+    /// Subscribe to one vault's change events as a stream, independent of
+    /// the `vault-changes` event emitted to the frontend. Intended for
+    /// in-process consumers (e.g. an index that wants incremental updates)
+    /// that shouldn't have to round-trip through the webview. The broadcast
+    /// channel backing this is shared across every watched vault, so the
+    /// stream is filtered down to `vault_id` here rather than leaving that
+    /// to each consumer.
+    pub fn subscribe(&self, vault_id: Uuid) -> impl Stream<Item = FileSystemEvent> {
+        BroadcastStream::new(self.changes.subscribe())
+            .filter_map(|event| event.ok())
+            .filter(move |event| event.vault_id == vault_id)
+    }
+
     pub async fn watch_vault(&self, vault_id: Uuid, vault_path: PathBuf) -> Result<()> {
-        // Stop watching any existing vault first
-        self.unwatch_current_vault().await?;
+        self.watch_vault_with(vault_id, vault_path, WatcherKind::Native)
+            .await
+    }
+
+    /// Like [`Self::watch_vault`], but lets the caller pick the watcher
+    /// backend explicitly, so a vault on a network share or FUSE mount where
+    /// the native backend doesn't deliver events can opt into polling.
+    ///
+    /// Vaults are watched independently of one another: each gets its own
+    /// `WatcherState` keyed by `vault_id` and its own event-processing task,
+    /// so opening a second vault doesn't stop live updates for the first.
+    pub async fn watch_vault_with(
+        &self,
+        vault_id: Uuid,
+        vault_path: PathBuf,
+        kind: WatcherKind,
+    ) -> Result<()> {
+        // Re-watching the same vault replaces its existing watcher instead
+        // of running two tasks against it.
+        self.unwatch_vault(&vault_id).await?;
 
         let (tx, mut rx) = mpsc::channel(100);
         let debounce_duration = Duration::from_millis(200);
 
         // Create the watcher
-        let mut watcher = RecommendedWatcher::new(
-            move |res: Result<Event, notify::Error>| {
-                if let Err(e) = tx.blocking_send(res) {
-                    error!("Failed to send event: {}", e);
-                }
-            },
-            Config::default(),
-        )?;
+        let mut watcher: Box<dyn Watcher + Send> = match kind {
+            WatcherKind::Native => Box::new(RecommendedWatcher::new(
+                move |res: Result<Event, notify::Error>| {
+                    if let Err(e) = tx.blocking_send(res) {
+                        error!("Failed to send event: {}", e);
+                    }
+                },
+                Config::default(),
+            )?),
+            WatcherKind::Poll(interval) => Box::new(PollWatcher::new(
+                move |res: Result<Event, notify::Error>| {
+                    if let Err(e) = tx.blocking_send(res) {
+                        error!("Failed to send event: {}", e);
+                    }
+                },
+                Config::default().with_poll_interval(interval),
+            )?),
+        };
 
         // Watch the vault directory
         watcher.watch(&vault_path, RecursiveMode::Recursive)?;
 
-        let watcher_state = WatcherState {
-            vault_id,
-            watcher,
-            vault_path: vault_path.clone(),
-        };
-
-        // Store the watcher
-        {
-            let mut current = self.current_watcher.write().await;
-            *current = Some(watcher_state);
-        }
+        let ignore = Arc::new(RwLock::new(IgnoreMatcher::load(&vault_path)?));
 
-        // Process events in a separate task
+        // Process events in a separate task, one per watched vault.
         let app_handle = self.app_handle.clone();
         let pending_ops = self.pending_operations.clone();
         let vault_path_clone = vault_path.clone();
+        let changes = self.changes.clone();
+        let ignore_clone = ignore.clone();
 
-        tokio::spawn(async move {
-            let mut event_buffer: Vec<FileSystemEvent> = Vec::new();
-            let mut last_emit = tokio::time::Instant::now();
+        let task = tokio::spawn(async move {
+            let mut path_states: HashMap<(Uuid, String), PathState> = HashMap::new();
+            let mut file_id_cache: HashMap<PathBuf, FileId> = HashMap::new();
+            let mut removed_ids: HashMap<FileId, (String, Instant)> = HashMap::new();
 
             loop {
                 // Wait for events with timeout
                 match timeout(debounce_duration, rx.recv()).await {
                     Ok(Some(Ok(event))) => {
-                        if let Some(fs_event) =
-                            process_notify_event(event, &vault_id, &vault_path_clone, &pending_ops)
-                                .await
+                        if let Some(classified) = process_notify_event(
+                            event,
+                            &vault_path_clone,
+                            vault_id,
+                            &pending_ops,
+                            &ignore_clone,
+                        )
+                        .await
                         {
-                            event_buffer.push(fs_event);
+                            purge_stale_removed_ids(&mut removed_ids, debounce_duration);
+                            correlate_rename(
+                                classified,
+                                &vault_id,
+                                &mut file_id_cache,
+                                &mut removed_ids,
+                                &mut path_states,
+                            );
                         }
                     }
                     Ok(Some(Err(e))) => {
                         error!("Watch error: {}", e);
+                        let diagnostic = WatcherDiagnostic {
+                            vault_id,
+                            message: format!(
+                                "Vault watcher backend reported an error ({e}); if this \
+                                 persists, this vault's filesystem may need polling mode."
+                            ),
+                        };
+                        if let Err(emit_err) = app_handle.emit("vault-watcher-diagnostic", &diagnostic) {
+                            error!("Failed to emit watcher diagnostic: {}", emit_err);
+                        }
                     }
                     Ok(None) => {
                         info!("Watcher channel closed");
                         break;
                     }
                     Err(_) => {
-                        // Timeout - check if we should emit buffered events
-                        if !event_buffer.is_empty() && last_emit.elapsed() >= debounce_duration {
-                            // Deduplicate events
-                            let unique_events = deduplicate_events(event_buffer.clone());
-
-                            // Emit events
-                            if let Err(e) = app_handle.emit("vault-changes", &unique_events) {
-                                error!("Failed to emit vault changes: {}", e);
-                            }
-
-                            event_buffer.clear();
-                            last_emit = tokio::time::Instant::now();
-                        }
+                        // Just a debounce-interval tick with nothing new;
+                        // fall through to the per-path flush check below.
                     }
                 }
+
+                flush_ready_paths(&mut path_states, debounce_duration, &app_handle, &changes);
             }
         });
 
-        Ok(())
-    }
+        // Keep the content index live from this vault's own change events
+        // instead of rescanning the vault on every change. Scoped to
+        // `vault_id` via `subscribe` so opening a second vault doesn't also
+        // feed the first vault's merge/index work with the second's events.
+        let index_app_handle = self.app_handle.clone();
+        let mut index_changes = self.subscribe(vault_id);
+        let index_task = tokio::spawn(async move {
+            while let Some(event) = index_changes.next().await {
+                let manager = index_app_handle.state::<VaultManager>();
 
-    pub async fn unwatch_vault(&self, vault_id: &Uuid) -> Result<()> {
-        let mut current = self.current_watcher.write().await;
-        if let Some(ref mut watcher_state) = *current {
-            if &watcher_state.vault_id == vault_id {
-                // Properly unwatch the path
-                if let Err(e) = watcher_state.watcher.unwatch(&watcher_state.vault_path) {
+                // Merge externally observed edits into the note's CRDT
+                // state (and, if the merge changed anything, rewrite the
+                // file) before indexing, so the index sees the merged text.
+                if let Err(e) = manager.merge_external_change(&event).await {
                     error!(
-                        "Failed to unwatch path {:?}: {}",
-                        watcher_state.vault_path, e
+                        "Failed to merge external change for {} in vault {}: {}",
+                        event.path, event.vault_id, e
                     );
                 }
-                *current = None;
-                info!("Stopped watching vault: {}", vault_id);
+
+                manager.apply_index_event(&event).await;
+            }
+        });
+
+        let watcher_state = WatcherState {
+            vault_id,
+            watcher,
+            vault_path: vault_path.clone(),
+            ignore,
+            task,
+            index_task,
+        };
+
+        let mut watchers = self.watchers.write().await;
+        watchers.insert(vault_id, watcher_state);
+
+        Ok(())
+    }
+
+    /// Stop watching `vault_id`, aborting its event-processing and
+    /// index-feeding tasks. A no-op if that vault isn't currently watched.
+    pub async fn unwatch_vault(&self, vault_id: &Uuid) -> Result<()> {
+        let mut watchers = self.watchers.write().await;
+        if let Some(mut watcher_state) = watchers.remove(vault_id) {
+            watcher_state.task.abort();
+            watcher_state.index_task.abort();
+            if let Err(e) = watcher_state.watcher.unwatch(&watcher_state.vault_path) {
+                error!(
+                    "Failed to unwatch path {:?}: {}",
+                    watcher_state.vault_path, e
+                );
             }
+            info!("Stopped watching vault: {}", vault_id);
         }
+        self.pending_operations.write().await.remove(vault_id);
         Ok(())
     }
 
-    async fn unwatch_current_vault(&self) -> Result<()> {
-        let mut current = self.current_watcher.write().await;
-        if let Some(mut watcher_state) = current.take() {
-            // Properly unwatch the path
+    /// Stop watching every currently watched vault, for app shutdown.
+    pub async fn unwatch_all(&self) -> Result<()> {
+        let mut watchers = self.watchers.write().await;
+        for (vault_id, mut watcher_state) in watchers.drain() {
+            watcher_state.task.abort();
+            watcher_state.index_task.abort();
             if let Err(e) = watcher_state.watcher.unwatch(&watcher_state.vault_path) {
                 error!(
                     "Failed to unwatch path {:?}: {}",
                     watcher_state.vault_path, e
                 );
             }
-            info!("Stopped watching vault: {}", watcher_state.vault_id);
+            info!("Stopped watching vault: {}", vault_id);
         }
+        self.pending_operations.write().await.clear();
         Ok(())
     }
 
-    pub async fn mark_pending_operation(&self, path: PathBuf) {
-        let mut pending: tokio::sync::RwLockWriteGuard<'_, HashSet<PathBuf>> =
-            self.pending_operations.write().await;
-        pending.insert(path.clone());
+    /// Add a runtime ignore pattern (gitignore glob syntax) for `vault_id`,
+    /// on top of whatever `.inkryptignore` already excludes. Takes effect
+    /// immediately for events processed afterward. Errors if `vault_id`
+    /// isn't currently watched.
+    pub async fn add_ignore_pattern(&self, vault_id: &Uuid, pattern: &str) -> Result<()> {
+        let watchers = self.watchers.read().await;
+        let Some(watcher_state) = watchers.get(vault_id) else {
+            return Err(anyhow::anyhow!("vault {} is not being watched", vault_id));
+        };
+        watcher_state.ignore.write().await.add_pattern(pattern)
+    }
+
+    pub async fn mark_pending_operation(&self, vault_id: Uuid, path: PathBuf) {
+        {
+            let mut pending = self.pending_operations.write().await;
+            pending.entry(vault_id).or_default().insert(path.clone());
+        }
 
         // Remove after a delay (preventing unnecessary UI re-renders)
         let pending_ops = self.pending_operations.clone();
@@ -159,35 +365,96 @@ impl VaultWatcher {
         tokio::spawn(async move {
             sleep(Duration::from_millis(500)).await;
             let mut pending = pending_ops.write().await;
-            pending.remove(&path_clone);
+            if let Some(paths) = pending.get_mut(&vault_id) {
+                paths.remove(&path_clone);
+            }
         });
     }
 }
 
+/// One filesystem change classified from a raw `notify::Event`, before
+/// [`correlate_rename`] has had a chance to merge a matching Delete+Create
+/// pair into a single `Rename`. `event_type` is only ever `Create`,
+/// `Modify`, or `Delete` here — classification never produces `Rename`
+/// directly.
+struct ClassifiedEvent {
+    event_type: FileEventType,
+    /// Absolute path, needed to stat a file-id for rename correlation.
+    path: PathBuf,
+    relative_path: String,
+    entry_type: Option<EntryType>,
+    details: Option<EventDetails>,
+}
+
+/// Map `notify`'s modify sub-kind to the coarser [`ModifyKind`] the frontend
+/// consumes, collapsing anything that isn't plain content/metadata/name
+/// changes (`ModifyKind::Any`, future variants) into `Other`.
+fn classify_modify_kind(kind: notify::event::ModifyKind) -> ModifyKind {
+    use notify::event::ModifyKind as NotifyModifyKind;
+    match kind {
+        NotifyModifyKind::Data(_) => ModifyKind::Data,
+        NotifyModifyKind::Metadata(_) => ModifyKind::Metadata,
+        NotifyModifyKind::Name(_) => ModifyKind::Name,
+        NotifyModifyKind::Any | NotifyModifyKind::Other => ModifyKind::Other,
+    }
+}
+
 async fn process_notify_event(
     event: Event,
-    vault_id: &Uuid,
     vault_path: &Path,
-    pending_operations: &Arc<RwLock<HashSet<PathBuf>>>,
-) -> Option<FileSystemEvent> {
-    // Check if this is a pending operation
+    vault_id: Uuid,
+    pending_operations: &Arc<RwLock<HashMap<Uuid, HashSet<PathBuf>>>>,
+    ignore: &Arc<RwLock<IgnoreMatcher>>,
+) -> Option<ClassifiedEvent> {
+    // Check if this is a pending operation. Scoped to this vault so a marked
+    // operation in one vault can't suppress a real change in another.
     {
         let pending = pending_operations.read().await;
-        for path in &event.paths {
-            if pending.contains(path) {
-                debug!("Ignoring pending operation for: {:?}", path);
-                return None;
+        if let Some(pending) = pending.get(&vault_id) {
+            for path in &event.paths {
+                if pending.contains(path) {
+                    debug!("Ignoring pending operation for: {:?}", path);
+                    return None;
+                }
             }
         }
     }
 
-    // Filter out .inkrypt directory changes
+    // Filter out .inkrypt and other dot-prefixed files/directories, consistent
+    // with the hidden-entry skipping in `VaultManager::list_entries`. Only the
+    // components relative to the vault root are checked, so a vault stored
+    // under a hidden ancestor directory (e.g. `~/.notes/myvault`) doesn't have
+    // every event dropped because of its own location.
     for path in &event.paths {
-        if path.components().any(|c| c.as_os_str() == ".inkrypt") {
+        let relative = path.strip_prefix(vault_path).unwrap_or(path);
+        if relative
+            .components()
+            .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        {
             return None;
         }
     }
 
+    // Filter out paths matched by `.inkryptignore` or a runtime pattern, so
+    // swap files, OS metadata files, and excluded media/build directories
+    // never reach `vault-changes`.
+    {
+        let matcher = ignore.read().await;
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(vault_path) else {
+                continue;
+            };
+            if matcher.is_ignored(relative, path.is_dir()) {
+                return None;
+            }
+        }
+    }
+
+    let modify_kind = match event.kind {
+        EventKind::Modify(kind) => Some(classify_modify_kind(kind)),
+        _ => None,
+    };
+
     let event_type = match event.kind {
         EventKind::Create(_) => FileEventType::Create,
         EventKind::Modify(_) => FileEventType::Modify,
@@ -197,32 +464,464 @@ async fn process_notify_event(
         EventKind::Other => return None,
     };
 
-    if let Some(path) = event.paths.first() {
-        if let Ok(relative_path) = path.strip_prefix(vault_path) {
-            let path_str = relative_path.to_string_lossy().replace('\\', "/");
+    let path = event.paths.first()?.clone();
+    let relative_path = path.strip_prefix(vault_path).ok()?.to_string_lossy().replace('\\', "/");
+
+    // Best-effort, and both derived from a single stat call: a path that
+    // vanished before we got to it (typically a Delete) simply has no
+    // `entry_type`/`details`, rather than failing the whole event.
+    let metadata = std::fs::metadata(&path).ok();
+    let entry_type = metadata.as_ref().map(|m| {
+        if m.is_dir() {
+            EntryType::Directory
+        } else {
+            EntryType::Note
+        }
+    });
+    let details = metadata.as_ref().map(|m| EventDetails {
+        timestamp: Utc::now(),
+        size: m.len(),
+        modified: system_time_to_utc(m.modified().ok()).unwrap_or_else(Utc::now),
+        modify_kind,
+    });
+
+    Some(ClassifiedEvent { event_type, path, relative_path, entry_type, details })
+}
 
-            return Some(FileSystemEvent {
-                event_type,
-                path: path_str,
-                vault_id: *vault_id,
+fn system_time_to_utc(time: Option<std::time::SystemTime>) -> Option<DateTime<Utc>> {
+    time.and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0))
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Correlate a classified Delete/Create pair by persistent file identity
+/// (inode+device on Unix, file index on Windows; see the `file-id` crate),
+/// merging a Delete immediately followed by a Create of the same underlying
+/// file into a single `Rename` instead of emitting both — so moving or
+/// renaming a note doesn't look like an unrelated delete-then-create to the
+/// rest of the app. `file_id_cache` is kept warm by stat'ing every
+/// Create/Modify path as it's seen, since a removed path can no longer be
+/// stat'd by the time its Delete arrives — the id has to already be on
+/// hand. `removed_ids` only ever holds entries from within the debounce
+/// window (see `purge_stale_removed_ids`, called before this), so an
+/// unrelated file reusing a freed id well after the fact won't be mistaken
+/// for a rename; if nothing matches, the Create is just a Create.
+fn correlate_rename(
+    classified: ClassifiedEvent,
+    vault_id: &Uuid,
+    file_id_cache: &mut HashMap<PathBuf, FileId>,
+    removed_ids: &mut HashMap<FileId, (String, Instant)>,
+    path_states: &mut HashMap<(Uuid, String), PathState>,
+) {
+    match classified.event_type {
+        FileEventType::Delete => {
+            if let Some(id) = file_id_cache.remove(&classified.path) {
+                removed_ids.insert(id, (classified.relative_path.clone(), Instant::now()));
+            }
+            record_event(
+                FileSystemEvent {
+                    event_type: FileEventType::Delete,
+                    path: classified.relative_path,
+                    vault_id: *vault_id,
+                    entry_type: None,
+                    details: None,
+                },
+                path_states,
+            );
+        }
+        FileEventType::Modify => {
+            if let Ok(id) = file_id::get_file_id(&classified.path) {
+                file_id_cache.insert(classified.path.clone(), id);
+            }
+            record_event(
+                FileSystemEvent {
+                    event_type: FileEventType::Modify,
+                    path: classified.relative_path,
+                    vault_id: *vault_id,
+                    entry_type: classified.entry_type,
+                    details: classified.details,
+                },
+                path_states,
+            );
+        }
+        FileEventType::Create => {
+            let matched_rename = file_id::get_file_id(&classified.path).ok().and_then(|id| {
+                file_id_cache.insert(classified.path.clone(), id);
+                removed_ids.remove(&id)
             });
+
+            let Some((from, _)) = matched_rename else {
+                record_event(
+                    FileSystemEvent {
+                        event_type: FileEventType::Create,
+                        path: classified.relative_path,
+                        vault_id: *vault_id,
+                        entry_type: classified.entry_type,
+                        details: classified.details,
+                    },
+                    path_states,
+                );
+                return;
+            };
+
+            // The old path's buffered state (most likely a pending Delete)
+            // is superseded by the rename; there's nothing left to flush
+            // for it under its old name.
+            path_states.remove(&(*vault_id, from.clone()));
+            record_event(
+                FileSystemEvent {
+                    event_type: FileEventType::Rename { from, to: classified.relative_path.clone() },
+                    path: classified.relative_path,
+                    vault_id: *vault_id,
+                    entry_type: classified.entry_type,
+                    details: classified.details,
+                },
+                path_states,
+            );
         }
+        FileEventType::Rename { .. } => unreachable!("classification never produces Rename"),
     }
+}
 
-    None
+/// Drop `removed_ids` entries older than the debounce `window`, so a
+/// Delete's file-id only stays eligible to correlate with a later Create
+/// for as long as they could plausibly be the same buffered rename.
+fn purge_stale_removed_ids(removed_ids: &mut HashMap<FileId, (String, Instant)>, window: Duration) {
+    removed_ids.retain(|_, (_, removed_at)| removed_at.elapsed() < window);
 }
 
-fn deduplicate_events(events: Vec<FileSystemEvent>) -> Vec<FileSystemEvent> {
-    let mut seen = HashSet::new();
-    let mut unique_events = Vec::new();
+/// Per-path debounce state: the first and last event classified for this
+/// path since it was last flushed, and when the last one arrived. Flushed
+/// (see `flush_ready_paths`/`finalize_path`) once `last_activity` has been
+/// quiet for the debounce interval, independent of every other path, so a
+/// burst on one note doesn't hold up — or get prematurely flushed with —
+/// unrelated changes elsewhere in the vault.
+struct PathState {
+    first_event: FileSystemEvent,
+    last_event: FileSystemEvent,
+    last_activity: Instant,
+}
+
+/// Coarse event kind, ignoring `Rename`'s payload, used to decide how a
+/// path's first and last observed events within one debounce window should
+/// collapse into the single event actually flushed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CollapseKind {
+    Create,
+    Modify,
+    Delete,
+    Rename,
+}
 
-    for event in events.into_iter().rev() {
-        let key = (event.vault_id, event.path.clone());
-        if seen.insert(key) {
-            unique_events.push(event);
+fn event_kind(event_type: &FileEventType) -> CollapseKind {
+    match event_type {
+        FileEventType::Create => CollapseKind::Create,
+        FileEventType::Modify => CollapseKind::Modify,
+        FileEventType::Delete => CollapseKind::Delete,
+        FileEventType::Rename { .. } => CollapseKind::Rename,
+    }
+}
+
+/// Record one (already rename-correlated) event against its path's
+/// debounce state, creating the state on first sight and otherwise just
+/// advancing `last_event`/`last_activity` — the actual create→modify /
+/// modify→delete / create→delete collapsing happens once at flush time in
+/// `finalize_path`, not here.
+fn record_event(event: FileSystemEvent, path_states: &mut HashMap<(Uuid, String), PathState>) {
+    let key = (event.vault_id, event.path.clone());
+    path_states
+        .entry(key)
+        .and_modify(|state| {
+            state.last_event = event.clone();
+            state.last_activity = Instant::now();
+        })
+        .or_insert_with(|| PathState {
+            first_event: event.clone(),
+            last_event: event,
+            last_activity: Instant::now(),
+        });
+}
+
+/// Collapse one path's buffered first/last event into the single event to
+/// flush, or `None` if the pair cancels out entirely: Create+Modify
+/// collapses to Create, Modify+Delete (and anything+Delete) collapses to
+/// Delete, and Create+Delete — a file that appeared and vanished within one
+/// debounce window, e.g. an editor's atomic-save temp file — is dropped,
+/// since nothing observable actually changed from outside that window.
+fn finalize_path(state: PathState) -> Option<FileSystemEvent> {
+    let first_kind = event_kind(&state.first_event.event_type);
+    let last_kind = event_kind(&state.last_event.event_type);
+
+    match (first_kind, last_kind) {
+        (CollapseKind::Create, CollapseKind::Delete) => None,
+        (CollapseKind::Create, _) => Some(FileSystemEvent {
+            event_type: FileEventType::Create,
+            ..state.last_event
+        }),
+        (_, CollapseKind::Delete) => Some(state.last_event),
+        (CollapseKind::Rename, _) => Some(state.first_event),
+        _ => Some(state.last_event),
+    }
+}
+
+/// Flush every path that's been quiet for at least `debounce_duration`,
+/// emitting the survivors to the frontend and any in-process subscribers in
+/// one batch. Checked once per loop iteration (so at least every
+/// `debounce_duration`, via the `timeout` on `rx.recv()`), rather than on a
+/// single global timer covering the whole buffer.
+fn flush_ready_paths(
+    path_states: &mut HashMap<(Uuid, String), PathState>,
+    debounce_duration: Duration,
+    app_handle: &AppHandle,
+    changes: &broadcast::Sender<FileSystemEvent>,
+) {
+    let ready: Vec<(Uuid, String)> = path_states
+        .iter()
+        .filter(|(_, state)| state.last_activity.elapsed() >= debounce_duration)
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    if ready.is_empty() {
+        return;
+    }
+
+    let flushed: Vec<FileSystemEvent> = ready
+        .into_iter()
+        .filter_map(|key| path_states.remove(&key))
+        .filter_map(finalize_path)
+        .collect();
+
+    if flushed.is_empty() {
+        return;
+    }
+
+    if let Err(e) = app_handle.emit("vault-changes", &flushed) {
+        error!("Failed to emit vault changes: {}", e);
+    }
+    for event in &flushed {
+        // No subscribers is the common case; ignore the error.
+        let _ = changes.send(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn classified(event_type: FileEventType, path: PathBuf, relative_path: &str) -> ClassifiedEvent {
+        ClassifiedEvent {
+            event_type,
+            path,
+            relative_path: relative_path.to_string(),
+            entry_type: Some(EntryType::Note),
+            details: None,
         }
     }
 
-    unique_events.reverse();
-    unique_events
+    #[test]
+    fn correlate_rename_merges_delete_then_create_of_the_same_file() {
+        let dir = TempDir::new().unwrap();
+        let old_path = dir.path().join("old.md");
+        let new_path = dir.path().join("new.md");
+        std::fs::write(&old_path, b"content").unwrap();
+
+        let vault_id = Uuid::now_v7();
+        let mut file_id_cache = HashMap::new();
+        let mut removed_ids = HashMap::new();
+        let mut path_states = HashMap::new();
+
+        // Warm the cache as if a prior Create/Modify event had already been
+        // seen for this path, then rename on disk before the Delete for the
+        // old path is processed — the id has to already be on hand.
+        file_id_cache.insert(old_path.clone(), file_id::get_file_id(&old_path).unwrap());
+        std::fs::rename(&old_path, &new_path).unwrap();
+
+        correlate_rename(
+            classified(FileEventType::Delete, old_path.clone(), "old.md"),
+            &vault_id,
+            &mut file_id_cache,
+            &mut removed_ids,
+            &mut path_states,
+        );
+        assert_eq!(path_states.len(), 1);
+        assert_eq!(
+            path_states[&(vault_id, "old.md".to_string())].last_event.event_type,
+            FileEventType::Delete
+        );
+
+        correlate_rename(
+            classified(FileEventType::Create, new_path, "new.md"),
+            &vault_id,
+            &mut file_id_cache,
+            &mut removed_ids,
+            &mut path_states,
+        );
+
+        assert_eq!(path_states.len(), 1);
+        let state = path_states.remove(&(vault_id, "new.md".to_string())).unwrap();
+        match finalize_path(state).unwrap().event_type {
+            FileEventType::Rename { from, to } => {
+                assert_eq!(from, "old.md");
+                assert_eq!(to, "new.md");
+            }
+            other => panic!("expected Rename, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn correlate_rename_falls_back_to_create_when_no_id_matches() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("new.md");
+        std::fs::write(&path, b"content").unwrap();
+
+        let vault_id = Uuid::now_v7();
+        let mut file_id_cache = HashMap::new();
+        let mut removed_ids = HashMap::new();
+        let mut path_states = HashMap::new();
+
+        correlate_rename(
+            classified(FileEventType::Create, path, "new.md"),
+            &vault_id,
+            &mut file_id_cache,
+            &mut removed_ids,
+            &mut path_states,
+        );
+
+        assert_eq!(path_states.len(), 1);
+        assert_eq!(
+            path_states[&(vault_id, "new.md".to_string())].last_event.event_type,
+            FileEventType::Create
+        );
+    }
+
+    #[test]
+    fn purge_stale_removed_ids_drops_entries_past_the_window() {
+        let mut removed_ids = HashMap::new();
+        removed_ids.insert(
+            file_id::get_file_id(std::env::current_dir().unwrap()).unwrap(),
+            (
+                "some/path.md".to_string(),
+                Instant::now() - Duration::from_secs(1),
+            ),
+        );
+
+        purge_stale_removed_ids(&mut removed_ids, Duration::from_millis(200));
+        assert!(removed_ids.is_empty());
+    }
+
+    fn event(event_type: FileEventType, vault_id: Uuid, path: &str) -> FileSystemEvent {
+        FileSystemEvent {
+            event_type,
+            path: path.to_string(),
+            vault_id,
+            entry_type: Some(EntryType::Note),
+            details: None,
+        }
+    }
+
+    #[test]
+    fn finalize_path_collapses_create_then_modify_to_create() {
+        let vault_id = Uuid::now_v7();
+        let mut path_states = HashMap::new();
+        record_event(event(FileEventType::Create, vault_id, "a.md"), &mut path_states);
+        record_event(event(FileEventType::Modify, vault_id, "a.md"), &mut path_states);
+
+        let state = path_states.remove(&(vault_id, "a.md".to_string())).unwrap();
+        assert_eq!(finalize_path(state).unwrap().event_type, FileEventType::Create);
+    }
+
+    #[test]
+    fn finalize_path_collapses_modify_then_delete_to_delete() {
+        let vault_id = Uuid::now_v7();
+        let mut path_states = HashMap::new();
+        record_event(event(FileEventType::Modify, vault_id, "a.md"), &mut path_states);
+        record_event(event(FileEventType::Delete, vault_id, "a.md"), &mut path_states);
+
+        let state = path_states.remove(&(vault_id, "a.md".to_string())).unwrap();
+        assert_eq!(finalize_path(state).unwrap().event_type, FileEventType::Delete);
+    }
+
+    #[test]
+    fn finalize_path_drops_create_then_delete_entirely() {
+        let vault_id = Uuid::now_v7();
+        let mut path_states = HashMap::new();
+        record_event(event(FileEventType::Create, vault_id, "a.md"), &mut path_states);
+        record_event(event(FileEventType::Delete, vault_id, "a.md"), &mut path_states);
+
+        let state = path_states.remove(&(vault_id, "a.md".to_string())).unwrap();
+        assert!(finalize_path(state).is_none());
+    }
+
+    #[test]
+    fn ignore_matcher_honors_inkryptignore_file() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(".inkryptignore"), b"attachments/\n").unwrap();
+
+        let matcher = IgnoreMatcher::load(dir.path()).unwrap();
+        assert!(matcher.is_ignored(Path::new("attachments/image.png"), false));
+        assert!(!matcher.is_ignored(Path::new("notes/todo.md"), false));
+    }
+
+    #[test]
+    fn ignore_matcher_add_pattern_takes_effect_immediately() {
+        let dir = TempDir::new().unwrap();
+        let mut matcher = IgnoreMatcher::load(dir.path()).unwrap();
+        assert!(!matcher.is_ignored(Path::new("notes.md~"), false));
+
+        matcher.add_pattern("*.md~").unwrap();
+        assert!(matcher.is_ignored(Path::new("notes.md~"), false));
+    }
+
+    #[test]
+    fn classify_modify_kind_maps_data_and_metadata_changes() {
+        use notify::event::{DataChange, MetadataKind, ModifyKind as NotifyModifyKind, RenameMode};
+
+        assert_eq!(
+            classify_modify_kind(NotifyModifyKind::Data(DataChange::Content)),
+            ModifyKind::Data
+        );
+        assert_eq!(
+            classify_modify_kind(NotifyModifyKind::Metadata(MetadataKind::Permissions)),
+            ModifyKind::Metadata
+        );
+        assert_eq!(
+            classify_modify_kind(NotifyModifyKind::Name(RenameMode::Both)),
+            ModifyKind::Name
+        );
+        assert_eq!(classify_modify_kind(NotifyModifyKind::Any), ModifyKind::Other);
+    }
+
+    #[test]
+    fn correlate_rename_carries_details_through_for_modify() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("note.md");
+        std::fs::write(&path, b"content").unwrap();
+
+        let vault_id = Uuid::now_v7();
+        let mut file_id_cache = HashMap::new();
+        let mut removed_ids = HashMap::new();
+        let mut path_states = HashMap::new();
+
+        let mut classified_event = classified(FileEventType::Modify, path.clone(), "note.md");
+        classified_event.details = Some(EventDetails {
+            timestamp: Utc::now(),
+            size: 7,
+            modified: Utc::now(),
+            modify_kind: Some(ModifyKind::Data),
+        });
+
+        correlate_rename(
+            classified_event,
+            &vault_id,
+            &mut file_id_cache,
+            &mut removed_ids,
+            &mut path_states,
+        );
+
+        let state = path_states.remove(&(vault_id, "note.md".to_string())).unwrap();
+        let details = finalize_path(state).unwrap().details.unwrap();
+        assert_eq!(details.size, 7);
+        assert_eq!(details.modify_kind, Some(ModifyKind::Data));
+    }
 }